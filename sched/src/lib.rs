@@ -0,0 +1,157 @@
+//! The order in which queued messages are handed out, kept apart from how
+//! workers wait for them.
+//!
+//! Nothing here takes a lock, spawns a thread or touches the clock: it needs
+//! only `core` and `alloc`, so it runs unchanged on a runtime without
+//! `std::thread`. `yarws` layers the locking, sleeping and capacity on top,
+//! and re-exports this crate as `yarws::sched`.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::{collections::BinaryHeap, vec::Vec};
+use core::{cmp::Ordering, mem};
+
+/// A message the scheduler can queue.
+pub trait Scheduled {
+    /// Whether this is a job rather than a control message. Control
+    /// messages are only handed out once no jobs are left.
+    fn is_job(&self) -> bool;
+}
+
+/// A queued message with its priority and the order it was pushed in.
+pub struct Entry<T> {
+    /// Higher priorities are handed out first.
+    pub priority: u8,
+    /// Breaks ties between equal priorities: lower numbers were pushed
+    /// earlier and are handed out first.
+    pub seq: u64,
+    /// The queued job or control message.
+    pub message: T,
+}
+
+impl<T: Scheduled> Entry<T> {
+    /// Whether the message is a job rather than a control message.
+    pub fn is_job(&self) -> bool {
+        self.message.is_job()
+    }
+}
+
+/// Greater entries are handed out first: jobs before control messages, then
+/// higher priorities, then earlier pushes.
+impl<T: Scheduled> Ord for Entry<T> {
+    fn cmp(&self, other: &Entry<T>) -> Ordering {
+        self.is_job()
+            .cmp(&other.is_job())
+            .then_with(|| self.priority.cmp(&other.priority))
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl<T: Scheduled> PartialOrd for Entry<T> {
+    fn partial_cmp(&self, other: &Entry<T>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Scheduled> PartialEq for Entry<T> {
+    fn eq(&self, other: &Entry<T>) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T: Scheduled> Eq for Entry<T> {}
+
+/// Entries shared by every worker, handed out in `Entry` order.
+pub struct Injector<T> {
+    heap: BinaryHeap<Entry<T>>,
+}
+
+impl<T: Scheduled> Injector<T> {
+    /// Creates an empty injector.
+    pub fn new() -> Injector<T> {
+        Injector {
+            heap: BinaryHeap::new(),
+        }
+    }
+
+    /// Queues one entry.
+    pub fn push(&mut self, entry: Entry<T>) {
+        self.heap.push(entry);
+    }
+
+    /// Queues every entry in `entries`.
+    pub fn extend(&mut self, entries: impl IntoIterator<Item = Entry<T>>) {
+        self.heap.extend(entries);
+    }
+
+    /// Returns the entry that would be handed out next.
+    pub fn peek(&self) -> Option<&Entry<T>> {
+        self.heap.peek()
+    }
+
+    /// Removes the entry that is handed out next.
+    pub fn pop(&mut self) -> Option<Entry<T>> {
+        self.heap.pop()
+    }
+
+    /// Returns the sequence number of the job pushed first, whatever its
+    /// priority.
+    pub fn oldest_job(&self) -> Option<u64> {
+        self.heap
+            .iter()
+            .filter(|entry| entry.is_job())
+            .map(|entry| entry.seq)
+            .min()
+    }
+
+    /// Removes the job pushed first, whatever its priority.
+    pub fn take_oldest_job(&mut self) -> Option<Entry<T>> {
+        let mut entries = mem::take(&mut self.heap).into_vec();
+        let index = entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.is_job())
+            .min_by_key(|(_, entry)| entry.seq)
+            .map(|(index, _)| index);
+        let entry = index.map(|index| entries.swap_remove(index));
+        self.heap = entries.into();
+
+        entry
+    }
+
+    /// Removes every job, leaving control messages queued.
+    pub fn take_jobs(&mut self) -> Vec<Entry<T>> {
+        let (jobs, rest): (Vec<_>, Vec<_>) = self.heap.drain().partition(Entry::is_job);
+        self.heap.extend(rest);
+
+        jobs
+    }
+}
+
+impl<T: Scheduled> Default for Injector<T> {
+    fn default() -> Injector<T> {
+        Injector::new()
+    }
+}
+
+/// Deals `entries` out round-robin into `shares` batches, the first going to
+/// batch `start % shares`.
+///
+/// # Panics
+///
+/// Panics if `shares` is zero.
+pub fn spread<T>(
+    entries: impl IntoIterator<Item = Entry<T>>,
+    shares: usize,
+    start: usize,
+) -> Vec<Vec<Entry<T>>> {
+    let mut batches: Vec<Vec<Entry<T>>> = (0..shares).map(|_| Vec::new()).collect();
+
+    for (i, entry) in entries.into_iter().enumerate() {
+        batches[(start + i) % shares].push(entry);
+    }
+
+    batches
+}
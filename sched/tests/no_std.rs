@@ -0,0 +1,93 @@
+//! Drives the scheduler without `std`: no threads, no locks, no clock. The
+//! test harness itself still needs `std`, which is the only reason it is
+//! linked.
+
+#![no_std]
+
+extern crate alloc;
+extern crate std;
+
+use alloc::vec::Vec;
+
+use yarws_sched::{spread, Entry, Injector, Scheduled};
+
+#[derive(Debug, PartialEq, Eq)]
+enum Message {
+    Job(&'static str),
+    Stop,
+}
+
+impl Scheduled for Message {
+    fn is_job(&self) -> bool {
+        matches!(self, Message::Job(_))
+    }
+}
+
+fn entry(priority: u8, seq: u64, message: Message) -> Entry<Message> {
+    Entry {
+        priority,
+        seq,
+        message,
+    }
+}
+
+fn drain(injector: &mut Injector<Message>) -> Vec<Message> {
+    let mut messages = Vec::new();
+    while let Some(entry) = injector.pop() {
+        messages.push(entry.message);
+    }
+
+    messages
+}
+
+#[test]
+fn jobs_pop_by_priority_then_push_order_before_control_messages() {
+    let mut injector = Injector::new();
+
+    injector.push(entry(0, 0, Message::Stop));
+    injector.push(entry(0, 1, Message::Job("low first")));
+    injector.push(entry(7, 2, Message::Job("high")));
+    injector.push(entry(0, 3, Message::Job("low second")));
+
+    assert_eq!(
+        drain(&mut injector),
+        [
+            Message::Job("high"),
+            Message::Job("low first"),
+            Message::Job("low second"),
+            Message::Stop,
+        ]
+    );
+}
+
+#[test]
+fn take_oldest_job_ignores_priority() {
+    let mut injector = Injector::new();
+
+    injector.push(entry(0, 4, Message::Job("old")));
+    injector.push(entry(9, 5, Message::Job("urgent")));
+    injector.push(entry(0, 0, Message::Stop));
+
+    assert_eq!(injector.oldest_job(), Some(4));
+    assert_eq!(
+        injector.take_oldest_job().map(|entry| entry.message),
+        Some(Message::Job("old"))
+    );
+    assert_eq!(
+        drain(&mut injector),
+        [Message::Job("urgent"), Message::Stop]
+    );
+}
+
+#[test]
+fn spread_deals_entries_round_robin() {
+    let entries = (0..5).map(|seq| entry(0, seq, Message::Job("job")));
+
+    let batches = spread(entries, 2, 1);
+
+    let seqs: Vec<Vec<u64>> = batches
+        .iter()
+        .map(|batch| batch.iter().map(|entry| entry.seq).collect())
+        .collect();
+    assert_eq!(seqs, [[1, 3].as_slice(), [0, 2, 4].as_slice()]);
+}
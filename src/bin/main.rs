@@ -1,10 +1,10 @@
 use std::{net::{TcpListener, TcpStream}, io::{Read, Write}, fs, thread, time::Duration};
 
-use threadpool::ThreadPool;
+use yarws::ThreadPool;
 
 fn main() {
     let listener = TcpListener::bind("localhost:8080").unwrap();
-    let pool = ThreadPool::new(4);
+    let pool = ThreadPool::new(4).unwrap();
 
     for stream in listener.incoming() {
         let stream = stream.unwrap();
@@ -21,7 +21,7 @@ fn main() {
 
 fn handle_connection(mut stream: TcpStream) {
     let mut buffer = [0; 1024];
-    stream.read(&mut buffer).unwrap();
+    let _ = stream.read(&mut buffer).unwrap();
 
     let get = b"GET / HTTP/1.1\r\n";
     let sleep = b"GET /sleep HTTP/1.1\r\n";
@@ -44,6 +44,6 @@ fn handle_connection(mut stream: TcpStream) {
         contents
     );
 
-    stream.write(response.as_bytes()).unwrap();
+    stream.write_all(response.as_bytes()).unwrap();
     stream.flush().unwrap();
 }
\ No newline at end of file
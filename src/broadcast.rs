@@ -0,0 +1,89 @@
+use std::{
+    any::Any,
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, PoisonError},
+};
+
+use crate::{
+    lock,
+    sync::{Condvar, Mutex},
+};
+
+/// A closure being run once on every worker by `ThreadPool::broadcast`.
+pub(crate) struct Broadcast {
+    f: Box<dyn Fn(usize) + Send + Sync>,
+    inner: Mutex<Inner>,
+    done: Condvar,
+}
+
+struct Inner {
+    /// Deliveries not yet run or dropped.
+    remaining: usize,
+    panic: Option<Box<dyn Any + Send + 'static>>,
+}
+
+/// A broadcast waiting in one worker's inbox.
+///
+/// Counts as done when dropped, so a worker that exits before running it
+/// does not hold up the caller.
+pub(crate) struct Delivery(Arc<Broadcast>);
+
+impl Broadcast {
+    pub(crate) fn new<F>(f: F) -> Arc<Broadcast>
+    where
+        F: Fn(usize) + Send + Sync + 'static,
+    {
+        Arc::new(Broadcast {
+            f: Box::new(f),
+            inner: Mutex::new(Inner {
+                remaining: 0,
+                panic: None,
+            }),
+            done: Condvar::new(),
+        })
+    }
+
+    /// Returns a delivery for one more worker.
+    pub(crate) fn deliver(self: &Arc<Self>) -> Delivery {
+        lock(&self.inner).remaining += 1;
+
+        Delivery(Arc::clone(self))
+    }
+
+    /// Blocks until every delivery has run or been dropped, then resumes the
+    /// first panic, if any.
+    pub(crate) fn wait(&self) {
+        let inner = lock(&self.inner);
+        let mut inner = self
+            .done
+            .wait_while(inner, |inner| inner.remaining > 0)
+            .unwrap_or_else(PoisonError::into_inner);
+
+        if let Some(payload) = inner.panic.take() {
+            drop(inner);
+            panic::resume_unwind(payload);
+        }
+    }
+}
+
+impl Delivery {
+    /// Runs the broadcast closure for worker `id`.
+    pub(crate) fn run(self, id: usize) {
+        let broadcast = &self.0;
+
+        if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| (broadcast.f)(id))) {
+            lock(&broadcast.inner).panic.get_or_insert(payload);
+        }
+    }
+}
+
+impl Drop for Delivery {
+    fn drop(&mut self) {
+        let mut inner = lock(&self.0.inner);
+        inner.remaining -= 1;
+
+        if inner.remaining == 0 {
+            self.0.done.notify_all();
+        }
+    }
+}
@@ -0,0 +1,577 @@
+use std::{any::Any, collections::HashMap, fmt, path::PathBuf, sync::Arc, time::Duration};
+
+use crate::{
+    custom::UserQueue, task::TaskHandler, CustomQueue, PoolCreationError, PoolEvent, SemaphoreKey,
+    TaskId, ThreadPool,
+};
+
+/// A user-supplied callback, shown opaquely in `Debug` output.
+pub(crate) struct Callback<F: ?Sized>(pub(crate) Box<F>);
+
+impl<F: ?Sized> fmt::Debug for Callback<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Callback")
+    }
+}
+
+/// What a worker does after a job it was running panics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PanicPolicy {
+    /// Catch the panic and keep using the same worker thread. This is the
+    /// default.
+    #[default]
+    Continue,
+    /// Let the worker thread exit and start a fresh one in its place.
+    Respawn,
+    /// Let the panic unwind the worker thread, which is not replaced. If no
+    /// workers are left, queued jobs never run.
+    Propagate,
+}
+
+/// How queued jobs are handed out to workers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dispatch {
+    /// Jobs are spread across the workers, and an idle worker steals jobs
+    /// from busy ones. This is the default.
+    #[default]
+    Shared,
+    /// Jobs are assigned to the workers in turn and never stolen, so each
+    /// worker gets an even share even if that leaves some of them idle.
+    ///
+    /// Jobs with a priority above 0 still go to whichever worker is free
+    /// first.
+    RoundRobin,
+    /// Jobs are started strictly in the order they were submitted, each by
+    /// whichever worker is free first, and then run in parallel.
+    ///
+    /// Only the start order is guaranteed: a short job can finish before a
+    /// longer one submitted ahead of it, and a job that has just started can
+    /// be overtaken by the next one before running its first line. Jobs with
+    /// a higher priority still start first. Every worker takes jobs from a
+    /// single shared queue, so workers contend on it more than with the
+    /// other modes.
+    FifoStart,
+}
+
+/// What `execute` does when a bounded queue is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// Block until a worker makes room. This is the default.
+    #[default]
+    Block,
+    /// Discard the job being submitted.
+    DropNewest,
+    /// Discard the job that has been queued the longest to make room. With a
+    /// custom queue, the job it would hand out next is discarded instead.
+    DropOldest,
+    /// Panic with `ExecuteError::Full`, like any other job that cannot be
+    /// submitted.
+    Error,
+}
+
+/// The order in which a shutting-down pool stops its workers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownOrder {
+    /// The first worker spawned is stopped first.
+    Fifo,
+    /// The last worker spawned is stopped first.
+    Lifo,
+}
+
+/// The OS scheduling priority of a pool's worker threads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ThreadPriority {
+    /// Below normal, so background work yields to other threads.
+    Low,
+    /// The default priority for new threads.
+    #[default]
+    Normal,
+    /// Above normal. This usually needs elevated privileges.
+    High,
+}
+
+/// Settings shared by every worker of a pool.
+#[derive(Debug, Default)]
+pub(crate) struct Config {
+    pub(crate) thread_name_prefix: Option<String>,
+    pub(crate) stack_size: Option<usize>,
+    pub(crate) queue_capacity: Option<usize>,
+    pub(crate) overflow_policy: OverflowPolicy,
+    pub(crate) inline_threshold: Option<usize>,
+    pub(crate) pin_to_cores: bool,
+    pub(crate) thread_priority: Option<ThreadPriority>,
+    pub(crate) warm_allocator: bool,
+    pub(crate) panic_policy: PanicPolicy,
+    pub(crate) suppress_panic_output: bool,
+    pub(crate) dispatch: Dispatch,
+    pub(crate) shutdown_order: Option<ShutdownOrder>,
+    /// Taken by the job queue when the pool is built.
+    pub(crate) custom_queue: Option<UserQueue>,
+    pub(crate) max_jobs_per_second: Option<u32>,
+    pub(crate) idle_timeout: Option<Duration>,
+    pub(crate) min_size: usize,
+    /// Set only for elastic pools.
+    pub(crate) max_size: Option<usize>,
+    pub(crate) job_timeout_warning: Option<Duration>,
+    pub(crate) max_jobs_per_worker: Option<u64>,
+    pub(crate) starvation_check: Option<Duration>,
+    pub(crate) auto_scale: Option<Duration>,
+    pub(crate) scale_thresholds: Option<(usize, usize)>,
+    /// The most workers the auto-scaler may grow the pool to.
+    pub(crate) scale_max: usize,
+    pub(crate) concurrency_limits: HashMap<SemaphoreKey, usize>,
+    pub(crate) max_concurrent_weight: Option<u32>,
+    pub(crate) task_handlers: HashMap<TaskId, TaskHandler>,
+    pub(crate) spill: Option<(usize, PathBuf)>,
+    pub(crate) on_event: Option<Callback<dyn Fn(PoolEvent) + Send + Sync>>,
+    pub(crate) worker_init: Option<Callback<dyn Fn(usize) + Send + Sync>>,
+    pub(crate) worker_cleanup: Option<Callback<dyn Fn(usize) + Send + Sync>>,
+    pub(crate) immediate: bool,
+    pub(crate) manual: bool,
+    pub(crate) context: Option<Arc<dyn Any + Send + Sync>>,
+}
+
+/// Configures and creates a `ThreadPool`.
+#[derive(Debug, Default)]
+pub struct ThreadPoolBuilder {
+    size: Option<usize>,
+    max_size: Option<usize>,
+    config: Config,
+}
+
+impl ThreadPoolBuilder {
+    pub fn new() -> ThreadPoolBuilder {
+        ThreadPoolBuilder::default()
+    }
+
+    /// Sets the number of threads in the pool. This is required.
+    pub fn size(mut self, size: usize) -> ThreadPoolBuilder {
+        self.size = Some(size);
+        self
+    }
+
+    /// Names each worker thread `{prefix}{id}`.
+    pub fn thread_name_prefix(mut self, prefix: impl Into<String>) -> ThreadPoolBuilder {
+        self.config.thread_name_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Sets the stack size, in bytes, of each worker thread.
+    pub fn stack_size(mut self, stack_size: usize) -> ThreadPoolBuilder {
+        self.config.stack_size = Some(stack_size);
+        self
+    }
+
+    /// Limits the number of jobs that can wait in the queue.
+    ///
+    /// Once the queue is full, `execute` blocks and `try_execute` fails until
+    /// a worker picks up a job; see `overflow_policy` for other ways to
+    /// handle a full queue. Jobs that a worker of the same pool submits
+    /// with `execute` never block and may take the queue past its capacity,
+    /// so a job that spawns more jobs cannot deadlock the pool. The capacity
+    /// must be non-zero. By default the queue is unbounded.
+    pub fn queue_capacity(mut self, capacity: usize) -> ThreadPoolBuilder {
+        self.config.queue_capacity = Some(capacity);
+        self
+    }
+
+    /// Sets what `execute` and the other blocking submit methods do while the
+    /// queue set with `queue_capacity` is full. Jobs discarded by
+    /// `OverflowPolicy::DropNewest` or `OverflowPolicy::DropOldest` never run
+    /// and are counted by `ThreadPool::dropped_count`. Has no effect on an
+    /// unbounded queue or on `try_execute`.
+    pub fn overflow_policy(mut self, policy: OverflowPolicy) -> ThreadPoolBuilder {
+        self.config.overflow_policy = policy;
+        self
+    }
+
+    /// Sets how many jobs may be queued before `execute_or_run` runs jobs on
+    /// the calling thread instead. Defaults to the number of workers.
+    pub fn inline_threshold(mut self, queued: usize) -> ThreadPoolBuilder {
+        self.config.inline_threshold = Some(queued);
+        self
+    }
+
+    /// Pins each worker thread to CPU core `id % num_cores`.
+    ///
+    /// Where thread affinity is not supported, a warning is logged and the
+    /// workers run unpinned. Off by default.
+    pub fn pin_to_cores(mut self, pin: bool) -> ThreadPoolBuilder {
+        self.config.pin_to_cores = pin;
+        self
+    }
+
+    /// Makes each worker allocate and free a small buffer when it starts, so
+    /// allocators with per-thread arenas set theirs up before the worker's
+    /// first job. Off by default.
+    pub fn warm_allocator(mut self, warm: bool) -> ThreadPoolBuilder {
+        self.config.warm_allocator = warm;
+        self
+    }
+
+    /// Sets the OS scheduling priority of each worker thread.
+    ///
+    /// This is currently only supported on Linux, where it sets the thread's
+    /// nice value. Elsewhere, or if the priority cannot be set, a warning is
+    /// logged and the workers keep the default priority.
+    pub fn thread_priority(mut self, priority: ThreadPriority) -> ThreadPoolBuilder {
+        self.config.thread_priority = Some(priority);
+        self
+    }
+
+    /// Sets what a worker does after a job panics. Defaults to
+    /// `PanicPolicy::Continue`.
+    pub fn panic_handler(mut self, policy: PanicPolicy) -> ThreadPoolBuilder {
+        self.config.panic_policy = policy;
+        self
+    }
+
+    /// Stops panics in jobs from printing the panic hook's message and
+    /// backtrace. Off by default.
+    ///
+    /// The pool still logs each panic and counts it in `PoolStats`. Only the
+    /// pool's worker threads are silenced: panics anywhere else, including in
+    /// jobs run on the caller's thread by an immediate pool, reach the panic
+    /// hook as usual.
+    pub fn suppress_panic_output(mut self, suppress: bool) -> ThreadPoolBuilder {
+        self.config.suppress_panic_output = suppress;
+        self
+    }
+
+    /// Sets how queued jobs are handed out to workers. Defaults to
+    /// `Dispatch::Shared`.
+    pub fn dispatch(mut self, dispatch: Dispatch) -> ThreadPoolBuilder {
+        self.config.dispatch = dispatch;
+        self
+    }
+
+    /// Stops workers one at a time, in `order`, when the pool shuts down.
+    ///
+    /// Once the queue has been drained, each worker is told to stop and is
+    /// joined before the next one is told, so `worker_cleanup` runs in that
+    /// order too. `shutdown_timeout` tells every worker in order without
+    /// waiting between them. By default all workers stop together, in no
+    /// particular order.
+    pub fn shutdown_order(mut self, order: ShutdownOrder) -> ThreadPoolBuilder {
+        self.config.shutdown_order = Some(order);
+        self
+    }
+
+    /// Keeps queued jobs in `queue` instead of the built-in queue, so it
+    /// decides which job the next free worker runs.
+    ///
+    /// The pool still handles waiting, `queue_capacity`, pausing and
+    /// shutdown. The `dispatch` setting is ignored. By default the built-in
+    /// work-stealing queue is used; `StdQueue` keeps its order in a single
+    /// shared queue.
+    pub fn custom_queue(mut self, queue: impl CustomQueue + 'static) -> ThreadPoolBuilder {
+        self.config.custom_queue = Some(UserQueue(Box::new(queue)));
+        self
+    }
+
+    /// Limits how many jobs the workers start per second, across the whole
+    /// pool.
+    ///
+    /// A worker that would exceed the limit sleeps until it may start its
+    /// next job. Up to `rate` jobs can start at once after a quiet spell. The
+    /// rate must be non-zero. By default there is no limit.
+    pub fn max_jobs_per_second(mut self, rate: u32) -> ThreadPoolBuilder {
+        self.config.max_jobs_per_second = Some(rate);
+        self
+    }
+
+    /// Makes the pool elastic: a worker that finds no work for `timeout`
+    /// exits, down to `min_size` workers.
+    ///
+    /// When a job is submitted while no worker is waiting for one, the pool
+    /// spawns workers again, up to `max_size`. Without an idle timeout, the
+    /// pool still grows up to `max_size` if one was set.
+    pub fn idle_timeout(mut self, timeout: Duration) -> ThreadPoolBuilder {
+        self.config.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the number of workers an elastic pool keeps even when idle.
+    /// Defaults to 0.
+    pub fn min_size(mut self, min_size: usize) -> ThreadPoolBuilder {
+        self.config.min_size = min_size;
+        self
+    }
+
+    /// Sets the number of workers an elastic pool may grow to. Defaults to
+    /// the pool's initial size.
+    pub fn max_size(mut self, max_size: usize) -> ThreadPoolBuilder {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// Replaces each worker with a fresh thread after it has run `jobs`
+    /// jobs, so per-thread state such as thread-locals or allocator arenas
+    /// cannot grow without bound.
+    ///
+    /// The new thread keeps the worker's id and runs `worker_init` again, so
+    /// the pool's size never changes. The count must be non-zero. By default
+    /// workers are never replaced.
+    pub fn max_jobs_per_worker(mut self, jobs: u64) -> ThreadPoolBuilder {
+        self.config.max_jobs_per_worker = Some(jobs);
+        self
+    }
+
+    /// Warns about jobs that run for longer than `threshold`.
+    ///
+    /// A monitor thread logs a warning, and emits `PoolEvent::JobOverdue`,
+    /// once for each job that overruns. The job itself is left running.
+    pub fn job_timeout_warning(mut self, threshold: Duration) -> ThreadPoolBuilder {
+        self.config.job_timeout_warning = Some(threshold);
+        self
+    }
+
+    /// Warns when jobs are left waiting in the queue while some workers are
+    /// not running anything.
+    ///
+    /// A monitor thread checks every `interval`, and once the queue has been
+    /// non-empty with fewer jobs running than there are workers for two
+    /// checks in a row, it logs a warning and emits
+    /// `PoolEvent::QueueStarved`. Each spell is reported once. A paused pool
+    /// is not reported.
+    pub fn starvation_check(mut self, interval: Duration) -> ThreadPoolBuilder {
+        self.config.starvation_check = Some(interval);
+        self
+    }
+
+    /// Resizes the pool to match the load on it.
+    ///
+    /// A monitor thread checks the queue every `interval`. Once more jobs
+    /// than the high threshold set with `scale_thresholds` have been queued
+    /// for three checks in a row, it adds a worker, up to `max_size`. Once no
+    /// more jobs than the low threshold have been queued while some workers
+    /// were idle for three checks in a row, it removes one whenever a worker
+    /// is free, down to `min_size` but never below one worker. After each
+    /// change it waits another three checks, so the pool does not flap
+    /// between sizes. A paused pool is left alone.
+    ///
+    /// Unlike with `idle_timeout`, setting `max_size` does not make the pool
+    /// grow as soon as a job is submitted.
+    pub fn auto_scale(mut self, interval: Duration) -> ThreadPoolBuilder {
+        self.config.auto_scale = Some(interval);
+        self
+    }
+
+    /// Sets the queue depths at which `auto_scale` shrinks and grows the
+    /// pool: it shrinks with at most `low` jobs queued and grows with more
+    /// than `high`. `low` must be below `high`. Defaults to shrinking with
+    /// an empty queue and growing with more jobs queued than there are
+    /// workers.
+    pub fn scale_thresholds(mut self, low: usize, high: usize) -> ThreadPoolBuilder {
+        self.config.scale_thresholds = Some((low, high));
+        self
+    }
+
+    /// Lets at most `limit` jobs submitted with `execute_limited` under `key`
+    /// run at once, however many workers the pool has.
+    ///
+    /// A worker that picks up such a job while the limit is reached waits for
+    /// a permit before running it, and permits go out in the order the jobs
+    /// were picked up. The limit must be non-zero.
+    pub fn concurrency_limit(
+        mut self,
+        key: impl Into<SemaphoreKey>,
+        limit: usize,
+    ) -> ThreadPoolBuilder {
+        self.config.concurrency_limits.insert(key.into(), limit);
+        self
+    }
+
+    /// Registers `handler` to run the tasks submitted under `task` with
+    /// `ThreadPool::execute_task`. Each run is passed the task's payload.
+    pub fn task_handler(
+        mut self,
+        task: TaskId,
+        handler: Box<dyn Fn(Vec<u8>) + Send + Sync>,
+    ) -> ThreadPoolBuilder {
+        self.config
+            .task_handlers
+            .insert(task, TaskHandler(Arc::from(handler)));
+        self
+    }
+
+    /// Writes tasks submitted with `ThreadPool::execute_task` to a file in
+    /// `dir` while `threshold` or more jobs are waiting in the queue, and
+    /// loads them back in order as the queue drains.
+    ///
+    /// Only a task's id and payload are written, which is why spilling is
+    /// limited to registered tasks. The file is created on the first spill
+    /// and removed when the pool is dropped. Spilled tasks count towards
+    /// `wait_for_idle` but not `queued_count`. The threshold must be
+    /// non-zero. By default nothing is spilled.
+    pub fn spill_to_disk(mut self, threshold: usize, dir: impl Into<PathBuf>) -> ThreadPoolBuilder {
+        self.config.spill = Some((threshold, dir.into()));
+        self
+    }
+
+    /// Caps the total weight of jobs submitted with `execute_weighted` that
+    /// run at once.
+    ///
+    /// A worker that picks up a weighted job waits until the job fits under
+    /// the cap before running it. Waiting jobs go in the order they were
+    /// picked up, so a heavy job is not overtaken by lighter ones that would
+    /// still fit. The cap must be non-zero. By default there is none.
+    pub fn max_concurrent_weight(mut self, max: u32) -> ThreadPoolBuilder {
+        self.config.max_concurrent_weight = Some(max);
+        self
+    }
+
+    /// Calls `callback` for every `PoolEvent`.
+    ///
+    /// The callback runs inline on whichever thread caused the event, usually
+    /// a worker, so it should be cheap. If it panics, the panic is logged and
+    /// otherwise ignored.
+    pub fn on_event(mut self, callback: Box<dyn Fn(PoolEvent) + Send + Sync>) -> ThreadPoolBuilder {
+        self.config.on_event = Some(Callback(callback));
+        self
+    }
+
+    /// Calls `init` with the worker's id on each worker thread before it
+    /// takes its first job.
+    ///
+    /// Use this to set up per-thread resources that jobs then reach through
+    /// `thread_local!`. It also runs for workers started later, by
+    /// `add_workers`, `PanicPolicy::Respawn` or an elastic pool. If `init`
+    /// panics while the pool is being built, `build` fails; a worker started
+    /// later whose `init` panics exits without taking any jobs.
+    pub fn worker_init(mut self, init: Box<dyn Fn(usize) + Send + Sync>) -> ThreadPoolBuilder {
+        self.config.worker_init = Some(Callback(init));
+        self
+    }
+
+    /// Calls `cleanup` with the worker's id on each worker thread right
+    /// before it exits.
+    ///
+    /// This pairs with `worker_init` for releasing per-thread resources. It
+    /// runs however the worker stops: at shutdown, through `remove_workers`,
+    /// after an idle timeout, or after a panic that ends the thread. It does
+    /// not run on a worker whose `worker_init` panicked. A panic in `cleanup`
+    /// is logged and otherwise ignored.
+    pub fn worker_cleanup(
+        mut self,
+        cleanup: Box<dyn Fn(usize) + Send + Sync>,
+    ) -> ThreadPoolBuilder {
+        self.config.worker_cleanup = Some(Callback(cleanup));
+        self
+    }
+
+    /// Stores `context` in the pool for jobs submitted with
+    /// `execute_with_ctx` to borrow, so they need not each capture a clone
+    /// of the same shared state.
+    pub fn context<C>(mut self, context: C) -> ThreadPoolBuilder
+    where
+        C: Send + Sync + 'static,
+    {
+        self.config.context = Some(Arc::new(context));
+        self
+    }
+
+    /// Runs every job synchronously on the thread that submits it, without
+    /// spawning any workers. Off by default.
+    ///
+    /// The pool keeps the same API, so tests can swap it in to get
+    /// deterministic ordering. The size is ignored and need not be set. A job
+    /// that panics is logged as under `PanicPolicy::Continue`. Because
+    /// nothing runs in the background, a job that blocks until something is
+    /// submitted after it never returns.
+    pub fn immediate(mut self, immediate: bool) -> ThreadPoolBuilder {
+        self.config.immediate = immediate;
+        self
+    }
+
+    /// Queues jobs without spawning any workers, so that nothing runs until
+    /// `ThreadPool::step` is called. Off by default.
+    ///
+    /// This single-steps the pool, letting a test check what has happened
+    /// between one job and the next. The size is ignored and need not be
+    /// set. Jobs are stepped through in the order workers would take them.
+    /// Waiting on the pool, for example with `wait_for_idle`, blocks until
+    /// another thread steps through the jobs.
+    #[cfg(feature = "manual")]
+    pub fn manual(mut self, manual: bool) -> ThreadPoolBuilder {
+        self.config.manual = manual;
+        self
+    }
+
+    /// Creates the pool.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PoolCreationError::ZeroSize` if the size is zero or was never
+    /// set outside immediate mode, `PoolCreationError::ZeroCapacity` if the
+    /// queue capacity is zero, `PoolCreationError::ZeroRate` if the job rate
+    /// limit is zero, `PoolCreationError::ZeroLimit` if a concurrency limit
+    /// or the maximum concurrent weight is zero,
+    /// `PoolCreationError::ZeroThreshold` if the spill threshold is zero,
+    /// `PoolCreationError::ZeroJobsPerWorker` if the number of jobs per
+    /// worker is zero, `PoolCreationError::SizeBounds` unless
+    /// `min_size <= size <= max_size`, `PoolCreationError::ScaleThresholds`
+    /// unless the low scale threshold is below the high one, or
+    /// `PoolCreationError::InitPanicked` if `worker_init` panicked.
+    pub fn build(mut self) -> Result<ThreadPool, PoolCreationError> {
+        if self.config.queue_capacity == Some(0) {
+            return Err(PoolCreationError::ZeroCapacity);
+        }
+        if self.config.max_jobs_per_second == Some(0) {
+            return Err(PoolCreationError::ZeroRate);
+        }
+        if self
+            .config
+            .concurrency_limits
+            .values()
+            .any(|&limit| limit == 0)
+            || self.config.max_concurrent_weight == Some(0)
+        {
+            return Err(PoolCreationError::ZeroLimit);
+        }
+        if self
+            .config
+            .spill
+            .as_ref()
+            .is_some_and(|&(threshold, _)| threshold == 0)
+        {
+            return Err(PoolCreationError::ZeroThreshold);
+        }
+        if self.config.max_jobs_per_worker == Some(0) {
+            return Err(PoolCreationError::ZeroJobsPerWorker);
+        }
+        if self
+            .config
+            .scale_thresholds
+            .is_some_and(|(low, high)| low >= high)
+        {
+            return Err(PoolCreationError::ScaleThresholds);
+        }
+
+        if self.config.immediate || self.config.manual {
+            return ThreadPool::with_config(0, self.config);
+        }
+
+        let size = match self.size {
+            Some(size) if size > 0 => size,
+            _ => return Err(PoolCreationError::ZeroSize),
+        };
+
+        let max_size = self.max_size.unwrap_or(size);
+        if self.config.min_size > size || size > max_size {
+            return Err(PoolCreationError::SizeBounds);
+        }
+        if self.config.auto_scale.is_some() {
+            self.config.scale_max = max_size;
+        }
+        // An auto-scaled pool leaves growing to the scaler unless it is
+        // elastic too.
+        if self.config.idle_timeout.is_some()
+            || (self.max_size.is_some() && self.config.auto_scale.is_none())
+        {
+            self.config.max_size = Some(max_size);
+        }
+
+        ThreadPool::with_config(size, self.config)
+    }
+}
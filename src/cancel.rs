@@ -0,0 +1,63 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    mpsc, Arc,
+};
+
+/// A flag for cancelling jobs submitted with `execute_cancellable`.
+///
+/// Clones share the same flag, so a job can hold one clone and check it
+/// while running, while the submitter cancels through another.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Cancels every job using this token.
+    ///
+    /// Jobs that have not started yet are skipped. Jobs that are already
+    /// running keep going unless they check `is_cancelled` themselves.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns whether the token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// Reports whether a cancellable job ran, sending `false` if the job is
+/// dropped before it starts.
+pub(crate) struct Outcome(Option<mpsc::Sender<bool>>);
+
+impl Outcome {
+    pub(crate) fn new() -> (Outcome, mpsc::Receiver<bool>) {
+        let (sender, receiver) = mpsc::channel();
+
+        (Outcome(Some(sender)), receiver)
+    }
+
+    /// Runs `f`, then reports that the job ran.
+    pub(crate) fn run(mut self, f: impl FnOnce()) {
+        let sender = self.0.take();
+
+        f();
+
+        if let Some(sender) = sender {
+            let _ = sender.send(true);
+        }
+    }
+}
+
+impl Drop for Outcome {
+    fn drop(&mut self) {
+        if let Some(sender) = self.0.take() {
+            let _ = sender.send(false);
+        }
+    }
+}
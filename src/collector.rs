@@ -0,0 +1,133 @@
+use std::{
+    any::Any,
+    collections::BTreeMap,
+    mem,
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, PoisonError},
+};
+
+use crate::{
+    sync::{Condvar, Mutex, MutexGuard},
+    ThreadPoolHandle,
+};
+
+/// Collects the results of jobs by index, for gathering results
+/// incrementally instead of all at once with `ThreadPool::map`.
+///
+/// Created by `ThreadPool::collector`.
+pub struct Collector<T> {
+    handle: ThreadPoolHandle,
+    state: Arc<CollectorState<T>>,
+}
+
+struct CollectorState<T> {
+    inner: Mutex<Inner<T>>,
+    done: Condvar,
+}
+
+struct Inner<T> {
+    pending: usize,
+    results: BTreeMap<usize, T>,
+    panic: Option<Box<dyn Any + Send + 'static>>,
+}
+
+/// Marks a collected job as finished when dropped, whether or not it ran.
+struct Pending<T> {
+    state: Arc<CollectorState<T>>,
+}
+
+impl<T> Drop for Pending<T> {
+    fn drop(&mut self) {
+        let mut inner = self.state.lock();
+        inner.pending -= 1;
+
+        if inner.pending == 0 {
+            self.state.done.notify_all();
+        }
+    }
+}
+
+impl<T> CollectorState<T> {
+    fn lock(&self) -> MutexGuard<'_, Inner<T>> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<T: Send + 'static> Collector<T> {
+    pub(crate) fn new(handle: ThreadPoolHandle) -> Collector<T> {
+        Collector {
+            handle,
+            state: Arc::new(CollectorState {
+                inner: Mutex::new(Inner {
+                    pending: 0,
+                    results: BTreeMap::new(),
+                    panic: None,
+                }),
+                done: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Runs `f` on the pool and stores its result at `index`.
+    ///
+    /// Indices need not be submitted in order or be contiguous. If two jobs
+    /// use the same index before the next `collect`, the one that finishes
+    /// last wins.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the job queue has been closed because the pool is shutting
+    /// down.
+    pub fn submit<F>(&self, index: usize, f: F)
+    where
+        F: FnOnce() -> T + Send + 'static,
+    {
+        self.state.lock().pending += 1;
+
+        let pending = Pending {
+            state: Arc::clone(&self.state),
+        };
+
+        self.handle.execute(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(f));
+            let mut inner = pending.state.lock();
+
+            match result {
+                Ok(value) => {
+                    inner.results.insert(index, value);
+                }
+                Err(payload) => {
+                    inner.panic.get_or_insert(payload);
+                }
+            }
+            drop(inner);
+            drop(pending);
+        });
+    }
+
+    /// Blocks until every job submitted so far has finished, and returns
+    /// their results in index order.
+    ///
+    /// The collector is empty again afterwards, so it can be reused for
+    /// another batch. Jobs dropped without running because the pool shut
+    /// down leave no result. If any job panicked, the first panic is resumed
+    /// on the calling thread instead.
+    pub fn collect(&self) -> Vec<T> {
+        let inner = self.state.lock();
+        let mut inner = self
+            .state
+            .done
+            .wait_while(inner, |inner| inner.pending > 0)
+            .unwrap_or_else(PoisonError::into_inner);
+
+        let results = mem::take(&mut inner.results);
+        let panic = inner.panic.take();
+        drop(inner);
+
+        if let Some(payload) = panic {
+            panic::resume_unwind(payload);
+        }
+
+        results.into_values().collect()
+    }
+}
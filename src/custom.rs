@@ -0,0 +1,123 @@
+use std::fmt;
+
+use yarws_sched::{Entry, Injector, Scheduled};
+
+use crate::{
+    lock,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    QueuedJob,
+};
+
+/// Holds a pool's queued jobs in place of the built-in queue, set with
+/// `ThreadPoolBuilder::custom_queue`.
+///
+/// The queue only decides which job runs next. The pool still does the
+/// waiting, the capacity limit, pausing and shutdown, and keeps its own
+/// control messages elsewhere. Both methods are called from many threads at
+/// once and should not block for long.
+pub trait CustomQueue: Send + Sync {
+    /// Adds a job to the queue.
+    fn push(&self, job: PendingJob);
+
+    /// Takes the job that should run next, or returns `None` if the queue is
+    /// empty.
+    ///
+    /// Once `push` has returned, `pop` must not report the queue as empty
+    /// until that job has been taken.
+    fn pop(&self) -> Option<PendingJob>;
+}
+
+/// A job waiting in a `CustomQueue`.
+pub struct PendingJob {
+    pub(crate) job: QueuedJob,
+    pub(crate) priority: u8,
+}
+
+impl PendingJob {
+    /// Returns the priority the job was submitted with; see
+    /// `ThreadPool::execute_with_priority`.
+    pub fn priority(&self) -> u8 {
+        self.priority
+    }
+
+    /// Returns the name the job was submitted with, if any; see
+    /// `ThreadPool::execute_named`.
+    pub fn name(&self) -> Option<&str> {
+        self.job.name.as_deref()
+    }
+}
+
+impl fmt::Debug for PendingJob {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PendingJob")
+            .field("priority", &self.priority)
+            .field("name", &self.name())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Scheduled for PendingJob {
+    fn is_job(&self) -> bool {
+        true
+    }
+}
+
+/// A `CustomQueue` that hands out jobs the way the built-in queue does:
+/// higher priorities first, then in the order they were pushed.
+///
+/// Every worker takes jobs from this one queue, without the built-in queue's
+/// per-worker deques. It is a starting point for a queue that only changes
+/// part of the order, by wrapping it.
+pub struct StdQueue {
+    jobs: Mutex<Injector<PendingJob>>,
+    next_seq: AtomicU64,
+}
+
+impl StdQueue {
+    pub fn new() -> StdQueue {
+        StdQueue {
+            jobs: Mutex::new(Injector::new()),
+            next_seq: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Default for StdQueue {
+    fn default() -> StdQueue {
+        StdQueue::new()
+    }
+}
+
+impl CustomQueue for StdQueue {
+    fn push(&self, job: PendingJob) {
+        let entry = Entry {
+            priority: job.priority,
+            seq: self.next_seq.fetch_add(1, Ordering::Relaxed),
+            message: job,
+        };
+
+        lock(&self.jobs).push(entry);
+    }
+
+    fn pop(&self) -> Option<PendingJob> {
+        lock(&self.jobs).pop().map(|entry| entry.message)
+    }
+}
+
+impl fmt::Debug for StdQueue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StdQueue").finish_non_exhaustive()
+    }
+}
+
+/// A user-supplied queue, shown opaquely in `Debug` output.
+pub(crate) struct UserQueue(pub(crate) Box<dyn CustomQueue>);
+
+impl fmt::Debug for UserQueue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CustomQueue")
+    }
+}
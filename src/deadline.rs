@@ -0,0 +1,54 @@
+use std::{cell::Cell, time::Instant};
+
+/// Passed to a job submitted with `ThreadPool::execute_with_deadline`, so it
+/// can check whether its deadline has passed.
+#[derive(Debug)]
+pub struct DeadlineToken {
+    deadline: Instant,
+    observed: Cell<bool>,
+}
+
+impl DeadlineToken {
+    pub(crate) fn new(deadline: Instant) -> DeadlineToken {
+        DeadlineToken {
+            deadline,
+            observed: Cell::new(false),
+        }
+    }
+
+    /// Returns the job's deadline.
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+
+    /// Returns whether the deadline has passed.
+    ///
+    /// A job that sees `true` here should wrap up; it is then reported as
+    /// `DeadlineOutcome::Expired`.
+    pub fn expired(&self) -> bool {
+        let expired = Instant::now() >= self.deadline;
+
+        if expired {
+            self.observed.set(true);
+        }
+
+        expired
+    }
+
+    pub(crate) fn was_observed(&self) -> bool {
+        self.observed.get()
+    }
+}
+
+/// What happened to a job submitted with `ThreadPool::execute_with_deadline`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeadlineOutcome {
+    /// The job ran to completion without seeing its deadline pass.
+    Completed,
+    /// The deadline had passed before a worker started the job, so it never
+    /// ran.
+    Skipped,
+    /// The job ran, and `DeadlineToken::expired` told it the deadline had
+    /// passed.
+    Expired,
+}
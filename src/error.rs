@@ -0,0 +1,191 @@
+use std::{any::Any, error::Error, fmt};
+
+use crate::panic_message;
+
+/// Error returned when a `ThreadPool` cannot be created.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PoolCreationError {
+    /// The requested pool size was zero.
+    ZeroSize,
+    /// The requested queue capacity was zero.
+    ZeroCapacity,
+    /// The requested job rate limit was zero.
+    ZeroRate,
+    /// A requested concurrency limit or maximum weight was zero.
+    ZeroLimit,
+    /// The requested spill threshold was zero.
+    ZeroThreshold,
+    /// The requested number of jobs per worker was zero.
+    ZeroJobsPerWorker,
+    /// The sizes did not satisfy `min_size <= size <= max_size`.
+    SizeBounds,
+    /// The auto-scaling thresholds did not satisfy `low < high`.
+    ScaleThresholds,
+    /// The `worker_init` hook panicked on at least one worker.
+    InitPanicked,
+}
+
+impl fmt::Display for PoolCreationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PoolCreationError::ZeroSize => write!(f, "thread pool size must be greater than zero"),
+            PoolCreationError::ZeroCapacity => {
+                write!(f, "thread pool queue capacity must be greater than zero")
+            }
+            PoolCreationError::ZeroRate => {
+                write!(f, "thread pool job rate limit must be greater than zero")
+            }
+            PoolCreationError::ZeroLimit => {
+                write!(
+                    f,
+                    "thread pool concurrency limits must be greater than zero"
+                )
+            }
+            PoolCreationError::ZeroThreshold => {
+                write!(f, "thread pool spill threshold must be greater than zero")
+            }
+            PoolCreationError::ZeroJobsPerWorker => {
+                write!(f, "thread pool jobs per worker must be greater than zero")
+            }
+            PoolCreationError::SizeBounds => {
+                write!(
+                    f,
+                    "thread pool sizes must satisfy min_size <= size <= max_size"
+                )
+            }
+            PoolCreationError::ScaleThresholds => {
+                write!(f, "thread pool scale thresholds must satisfy low < high")
+            }
+            PoolCreationError::InitPanicked => write!(f, "thread pool worker init panicked"),
+        }
+    }
+}
+
+impl Error for PoolCreationError {}
+
+/// Error returned when a `ThreadPool` does not shut down in time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ShutdownError {
+    /// The deadline passed while `remaining` workers were still running.
+    Timeout { remaining: usize },
+}
+
+impl fmt::Display for ShutdownError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShutdownError::Timeout { remaining } => {
+                write!(f, "timed out with {} worker(s) still running", remaining)
+            }
+        }
+    }
+}
+
+impl Error for ShutdownError {}
+
+/// Error returned when a `ThreadPool` cannot be shrunk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ResizeError {
+    /// Removing that many workers would leave fewer than `min_size` workers,
+    /// or none, once the workers already retiring are gone; at most
+    /// `removable` can go.
+    TooFewWorkers { removable: usize },
+}
+
+impl fmt::Display for ResizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResizeError::TooFewWorkers { removable } => {
+                write!(
+                    f,
+                    "only {} worker(s) can be removed without going below \
+                     min_size or one worker, counting those already retiring",
+                    removable
+                )
+            }
+        }
+    }
+}
+
+impl Error for ResizeError {}
+
+/// Error returned when a job cannot be submitted to a `ThreadPool`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ExecuteError {
+    /// The job queue is at capacity.
+    Full,
+    /// The pool is shutting down and no longer accepts jobs.
+    ShutDown,
+    /// Every worker has died after a panic, so nothing would ever run the
+    /// job. See `ThreadPool::is_healthy`.
+    PoolDead,
+    /// No live worker has the id the job was sent to. See
+    /// `ThreadPool::execute_on`.
+    NoSuchWorker,
+}
+
+impl fmt::Display for ExecuteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExecuteError::Full => write!(f, "job queue is full"),
+            ExecuteError::ShutDown => write!(f, "thread pool is shut down"),
+            ExecuteError::PoolDead => write!(f, "thread pool has no workers left"),
+            ExecuteError::NoSuchWorker => write!(f, "no worker with the given id"),
+        }
+    }
+}
+
+impl Error for ExecuteError {}
+
+/// Error returned when joining a job that did not produce a value.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum JobError {
+    /// The job panicked; this holds the panic payload.
+    Panicked(Box<dyn Any + Send + 'static>),
+    /// The job was dropped without running, for example because the pool shut
+    /// down first.
+    Dropped,
+}
+
+impl fmt::Display for JobError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JobError::Panicked(payload) => write!(f, "job panicked: {}", panic_message(&**payload)),
+            JobError::Dropped => write!(f, "job was dropped without running"),
+        }
+    }
+}
+
+impl Error for JobError {}
+
+/// Error returned by `JobHandle::try_join_timeout`.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum TryJoinError {
+    /// The job did not finish in time.
+    Timeout,
+    /// The job finished without producing a value.
+    Job(JobError),
+}
+
+impl fmt::Display for TryJoinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryJoinError::Timeout => write!(f, "timed out waiting for job"),
+            TryJoinError::Job(err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for TryJoinError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TryJoinError::Timeout => None,
+            TryJoinError::Job(err) => Some(err),
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use std::time::Duration;
+
+/// Something that happened inside a `ThreadPool`.
+///
+/// Register a callback with `ThreadPoolBuilder::on_event` to receive these,
+/// for example to feed a metrics system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PoolEvent {
+    /// A job was added to the queue.
+    JobEnqueued,
+    /// A worker started running a job.
+    JobStarted { worker: usize },
+    /// A worker finished running a job, whether or not it panicked.
+    JobCompleted { worker: usize, duration: Duration },
+    /// A job panicked on a worker. What happens to the worker depends on the
+    /// pool's `PanicPolicy`.
+    WorkerPanicked { worker: usize },
+    /// A job has been running on a worker for longer than the threshold set
+    /// with `ThreadPoolBuilder::job_timeout_warning`.
+    JobOverdue { worker: usize, elapsed: Duration },
+    /// Jobs have been waiting in the queue while fewer jobs were running
+    /// than there are workers; see `ThreadPoolBuilder::starvation_check`.
+    QueueStarved { queued: usize, active: usize },
+    /// The pool began shutting down.
+    ShutdownStarted,
+}
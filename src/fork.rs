@@ -0,0 +1,153 @@
+use std::{
+    mem,
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, PoisonError},
+};
+
+use crate::{
+    lock,
+    sync::{thread, Condvar, Mutex},
+    Job, QueuedJob, Shared,
+};
+
+/// Where the pool half of a `fork_join` has got to.
+enum Fork<A, R> {
+    /// Queued; either side may still take `a`.
+    Pending(A),
+    /// A worker is running `a`.
+    Running,
+    /// The worker has finished running `a`.
+    Done(thread::Result<R>),
+    /// The caller has taken the result, or taken `a` back to run itself.
+    Taken,
+}
+
+struct ForkState<A, R> {
+    fork: Mutex<Fork<A, R>>,
+    done: Condvar,
+}
+
+impl<A, R> ForkState<A, R>
+where
+    A: FnOnce() -> R,
+{
+    fn new(a: A) -> ForkState<A, R> {
+        ForkState {
+            fork: Mutex::new(Fork::Pending(a)),
+            done: Condvar::new(),
+        }
+    }
+
+    /// Runs `a` on a worker, unless the caller has already taken it back.
+    fn run(&self) {
+        let a = {
+            let mut fork = lock(&self.fork);
+
+            match mem::replace(&mut *fork, Fork::Running) {
+                Fork::Pending(a) => a,
+                taken => {
+                    *fork = taken;
+                    return;
+                }
+            }
+        };
+
+        let result = panic::catch_unwind(AssertUnwindSafe(a));
+
+        *lock(&self.fork) = Fork::Done(result);
+        self.done.notify_one();
+    }
+
+    /// Returns the outcome of `a`, waiting for a worker that is running it
+    /// or running it on the calling thread if no worker has started it.
+    fn join(&self) -> thread::Result<R> {
+        let fork = {
+            let fork = lock(&self.fork);
+            let mut fork = self
+                .done
+                .wait_while(fork, |fork| matches!(fork, Fork::Running))
+                .unwrap_or_else(PoisonError::into_inner);
+
+            mem::replace(&mut *fork, Fork::Taken)
+        };
+
+        match fork {
+            Fork::Pending(a) => panic::catch_unwind(AssertUnwindSafe(a)),
+            Fork::Done(result) => result,
+            Fork::Running | Fork::Taken => unreachable!("fork_join state already taken"),
+        }
+    }
+}
+
+/// Runs `a` on the pool and `b` on the calling thread, returning both
+/// results.
+///
+/// If no worker has started `a` by the time `b` finishes, the caller takes
+/// `a` back and runs it too, so this never waits on a job that is still in
+/// the queue.
+pub(crate) fn fork_join<A, B, RA, RB>(shared: &Arc<Shared>, a: A, b: B) -> (RA, RB)
+where
+    A: FnOnce() -> RA + Send,
+    B: FnOnce() -> RB,
+    RA: Send,
+{
+    let state = Arc::new(ForkState::new(a));
+
+    let job_state = Arc::clone(&state);
+    let job: Box<dyn FnOnce() + Send + '_> = Box::new(move || job_state.run());
+
+    // SAFETY: the job only touches `a` and its result while the state is
+    // `Pending` or `Running`, and this function does not return until the
+    // state has moved past both. A job that runs after that finds `Taken`
+    // and returns without touching anything borrowed.
+    let job: Job = unsafe { mem::transmute::<Box<dyn FnOnce() + Send + '_>, Job>(job) };
+
+    // If the pool is shutting down the job is dropped unrun, and `a` is
+    // taken back below.
+    let _ = shared.submit(0, QueuedJob::new(job));
+
+    let b_result = panic::catch_unwind(AssertUnwindSafe(b));
+    let a_result = state.join();
+
+    match (a_result, b_result) {
+        (Ok(ra), Ok(rb)) => (ra, rb),
+        (Err(payload), _) | (_, Err(payload)) => panic::resume_unwind(payload),
+    }
+}
+
+/// Runs every task on the pool and returns their results in order.
+///
+/// The caller goes through the tasks in order, running any that no worker
+/// has started yet itself, so like `fork_join` this never waits on a job
+/// that is still in the queue.
+pub(crate) fn scatter_gather<F, R>(shared: &Arc<Shared>, tasks: Vec<F>) -> Vec<R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let states: Vec<Arc<ForkState<F, R>>> = tasks
+        .into_iter()
+        .map(|task| Arc::new(ForkState::new(task)))
+        .collect();
+
+    let jobs = states
+        .iter()
+        .map(|state| {
+            let state = Arc::clone(state);
+            let job: Job = Box::new(move || state.run());
+
+            QueuedJob::new(job)
+        })
+        .collect();
+
+    // If the pool is shutting down the jobs are dropped unrun, and the
+    // caller runs every task below.
+    let _ = shared.submit_all(jobs);
+
+    let results: Vec<thread::Result<R>> = states.iter().map(|state| state.join()).collect();
+
+    results
+        .into_iter()
+        .map(|result| result.unwrap_or_else(|payload| panic::resume_unwind(payload)))
+        .collect()
+}
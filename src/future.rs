@@ -0,0 +1,127 @@
+use std::{
+    any::Any,
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    pin::{pin, Pin},
+    sync::Arc,
+    task::{Context, Poll, Wake, Waker},
+};
+
+use crate::{
+    lock, JobError,
+    sync::{
+        thread::{self, Thread},
+        Mutex,
+    },
+};
+
+/// A future that resolves to the output of a job running on the pool.
+///
+/// Returned by `ThreadPool::spawn` and `ThreadPool::execute_future`. It can be
+/// awaited from any executor. It resolves to `JobError::Panicked` if the job
+/// panics, or to `JobError::Dropped` if the job is discarded without running,
+/// for example by `clear_queue` or `shutdown_now`.
+#[derive(Debug)]
+pub struct PoolFuture<T> {
+    slot: Arc<Mutex<Slot<T>>>,
+}
+
+#[derive(Debug)]
+struct Slot<T> {
+    outcome: Option<Outcome<T>>,
+    waker: Option<Waker>,
+}
+
+#[derive(Debug)]
+enum Outcome<T> {
+    Done(Result<T, Box<dyn Any + Send + 'static>>),
+    Dropped,
+}
+
+/// The job's end of a `PoolFuture`.
+pub(crate) struct Completer<T> {
+    slot: Arc<Mutex<Slot<T>>>,
+}
+
+impl<T> Completer<T> {
+    fn finish(&self, outcome: Outcome<T>) {
+        let mut slot = lock(&self.slot);
+
+        if slot.outcome.is_none() {
+            slot.outcome = Some(outcome);
+
+            if let Some(waker) = slot.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+
+    /// Runs `f`, catching a panic, and completes the future with the result.
+    pub(crate) fn complete(self, f: impl FnOnce() -> T) {
+        let result = panic::catch_unwind(AssertUnwindSafe(f));
+        self.finish(Outcome::Done(result));
+    }
+}
+
+impl<T> Drop for Completer<T> {
+    fn drop(&mut self) {
+        self.finish(Outcome::Dropped);
+    }
+}
+
+/// Creates a connected future and completer.
+pub(crate) fn pair<T>() -> (PoolFuture<T>, Completer<T>) {
+    let slot = Arc::new(Mutex::new(Slot {
+        outcome: None,
+        waker: None,
+    }));
+
+    let future = PoolFuture {
+        slot: Arc::clone(&slot),
+    };
+
+    (future, Completer { slot })
+}
+
+impl<T> Future for PoolFuture<T> {
+    type Output = Result<T, JobError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<T, JobError>> {
+        let mut slot = lock(&self.slot);
+
+        match slot.outcome.take() {
+            Some(Outcome::Done(result)) => Poll::Ready(result.map_err(JobError::Panicked)),
+            Some(Outcome::Dropped) => Poll::Ready(Err(JobError::Dropped)),
+            None => {
+                slot.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Polls `future` to completion on the current thread, parking between polls.
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use crate::{expect_submitted, CancellationToken, QueuedJob, ThreadPoolHandle};
+
+/// A set of jobs that can be cancelled together, such as the sub-jobs of one
+/// request.
+///
+/// Created by `ThreadPool::group`. Clones submit to the same group.
+#[derive(Debug, Clone)]
+pub struct JobGroup {
+    handle: ThreadPoolHandle,
+    token: CancellationToken,
+}
+
+impl JobGroup {
+    pub(crate) fn new(handle: ThreadPoolHandle) -> JobGroup {
+        JobGroup {
+            handle,
+            token: CancellationToken::new(),
+        }
+    }
+
+    /// Runs `f` on the pool as part of this group.
+    ///
+    /// If the group has already been cancelled, `f` is queued but skipped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the job queue has been closed because the pool is shutting
+    /// down.
+    pub fn execute<F>(&self, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let job = QueuedJob {
+            token: Some(self.token.clone()),
+            ..QueuedJob::new(Box::new(f))
+        };
+
+        expect_submitted(self.handle.shared.submit(0, job));
+    }
+
+    /// Cancels every job in the group that has not started yet.
+    ///
+    /// Those jobs are skipped when a worker picks them up. Jobs that are
+    /// already running are left to finish.
+    pub fn cancel_all(&self) {
+        self.token.cancel();
+    }
+
+    /// Returns whether `cancel_all` has been called.
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+}
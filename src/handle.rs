@@ -0,0 +1,97 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::mpsc,
+    time::Duration,
+};
+
+use crate::{CancellationToken, JobError, TryJoinError};
+
+/// Handle to the result of a job started with `ThreadPool::execute_with_handle`.
+///
+/// By default, dropping the handle without joining detaches the job: it
+/// still runs, and its result is discarded. See `cancel_on_drop` to cancel
+/// it instead.
+#[derive(Debug)]
+pub struct JobHandle<T> {
+    receiver: mpsc::Receiver<Result<T, JobError>>,
+    token: CancellationToken,
+    cancel_on_drop: bool,
+}
+
+/// Handle returned by `ThreadPool::spawn_blocking`.
+pub type BlockingHandle<T> = JobHandle<T>;
+
+impl<T> JobHandle<T> {
+    /// Wraps `f` so that its outcome is reported to the returned handle.
+    pub(crate) fn wrap<F>(f: F) -> (impl FnOnce() + Send + 'static, JobHandle<T>)
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+
+        let job = move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(f)).map_err(JobError::Panicked);
+            let _ = sender.send(result);
+        };
+
+        let handle = JobHandle {
+            receiver,
+            token: CancellationToken::new(),
+            cancel_on_drop: false,
+        };
+
+        (job, handle)
+    }
+
+    /// The token the job is queued with, for skipping it once cancelled.
+    pub(crate) fn token(&self) -> &CancellationToken {
+        &self.token
+    }
+
+    /// Makes dropping the handle cancel the job if it has not started yet.
+    ///
+    /// A cancelled job is skipped when a worker picks it up. Once the job
+    /// has started, dropping the handle no longer affects it.
+    pub fn cancel_on_drop(mut self) -> JobHandle<T> {
+        self.cancel_on_drop = true;
+        self
+    }
+
+    /// Blocks until the job has finished and returns its result.
+    ///
+    /// # Errors
+    ///
+    /// Returns `JobError::Panicked` with the panic payload if the job
+    /// panicked, or `JobError::Dropped` if the job was dropped without
+    /// running.
+    pub fn join(self) -> Result<T, JobError> {
+        self.receiver.recv().unwrap_or(Err(JobError::Dropped))
+    }
+
+    /// Waits at most `dur` for the job to finish and returns its result.
+    ///
+    /// On timeout the handle stays usable, so the caller can try again or
+    /// fall back to `join`. Once a result has been returned, later calls
+    /// report `JobError::Dropped`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TryJoinError::Timeout` if the job is still running after
+    /// `dur`, or `TryJoinError::Job` if it panicked or was dropped.
+    pub fn try_join_timeout(&self, dur: Duration) -> Result<T, TryJoinError> {
+        match self.receiver.recv_timeout(dur) {
+            Ok(result) => result.map_err(TryJoinError::Job),
+            Err(mpsc::RecvTimeoutError::Timeout) => Err(TryJoinError::Timeout),
+            Err(mpsc::RecvTimeoutError::Disconnected) => Err(TryJoinError::Job(JobError::Dropped)),
+        }
+    }
+}
+
+impl<T> Drop for JobHandle<T> {
+    fn drop(&mut self) {
+        if self.cancel_on_drop {
+            self.token.cancel();
+        }
+    }
+}
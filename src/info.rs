@@ -0,0 +1,12 @@
+use crate::sync::thread::ThreadId;
+
+/// A snapshot of one worker, from `ThreadPool::worker_info`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkerInfo {
+    /// The worker's id, as used in log messages and `PoolEvent`s.
+    pub id: usize,
+    /// The id of the worker's current thread.
+    pub thread_id: ThreadId,
+    /// Whether the thread was still running when the snapshot was taken.
+    pub alive: bool,
+}
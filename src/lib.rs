@@ -0,0 +1,3155 @@
+use std::{
+    any::Any,
+    cell::Cell,
+    cmp,
+    collections::HashMap,
+    fmt,
+    future::Future,
+    hint, mem,
+    panic::{self, AssertUnwindSafe},
+    ptr,
+    sync::{mpsc, Arc, Once, OnceLock, PoisonError},
+    time::{Duration, Instant},
+};
+
+use log::{debug, error, info, trace, warn};
+
+mod broadcast;
+mod builder;
+mod cancel;
+mod collector;
+mod custom;
+mod deadline;
+mod error;
+mod event;
+mod fork;
+mod future;
+mod group;
+mod handle;
+mod info;
+mod limit;
+mod queue;
+mod rate;
+mod scale;
+mod scope;
+mod sender;
+#[cfg(feature = "signal")]
+mod signal;
+mod starvation;
+mod stats;
+mod summary;
+mod sync;
+mod task;
+mod timer;
+mod watchdog;
+
+pub use builder::{
+    Dispatch, OverflowPolicy, PanicPolicy, ShutdownOrder, ThreadPoolBuilder, ThreadPriority,
+};
+pub use cancel::CancellationToken;
+pub use collector::Collector;
+pub use custom::{CustomQueue, PendingJob, StdQueue};
+pub use deadline::{DeadlineOutcome, DeadlineToken};
+pub use error::{
+    ExecuteError, JobError, PoolCreationError, ResizeError, ShutdownError, TryJoinError,
+};
+pub use event::PoolEvent;
+pub use future::PoolFuture;
+pub use group::JobGroup;
+pub use handle::{BlockingHandle, JobHandle};
+pub use info::WorkerInfo;
+pub use limit::SemaphoreKey;
+pub use scope::Scope;
+pub use sender::JobSender;
+pub use stats::PoolStats;
+pub use summary::ShutdownSummary;
+pub use task::TaskId;
+pub use timer::TaskHandle;
+pub use yarws_sched as sched;
+
+use broadcast::{Broadcast, Delivery};
+use builder::{Callback, Config};
+use limit::Semaphore;
+use queue::{JobQueue, Local, PushError};
+use rate::RateLimiter;
+use scale::AutoScaler;
+use starvation::StarvationMonitor;
+use sync::{
+    atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+    thread, thread_local, Condvar, Mutex, MutexGuard,
+};
+use task::Spill;
+use timer::Timer;
+use watchdog::{RunningJobs, Watchdog};
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Size of the buffer a worker allocates at startup with `warm_allocator`.
+const WARM_ALLOCATION_SIZE: usize = 64 * 1024;
+
+thread_local! {
+    /// The pool this thread is a worker of, if any. Only ever compared, never
+    /// dereferenced.
+    // Loom's `thread_local!` does not take a `const` initializer.
+    #[allow(clippy::missing_const_for_thread_local)]
+    static CURRENT_POOL: Cell<*const Shared> = Cell::new(ptr::null());
+
+    /// Set on worker threads of pools built with `suppress_panic_output`.
+    #[allow(clippy::missing_const_for_thread_local)]
+    static QUIET_PANICS: Cell<bool> = Cell::new(false);
+}
+
+/// A job waiting in the queue, along with its metadata.
+struct QueuedJob {
+    job: Job,
+    name: Option<String>,
+    /// Skips the job if cancelled before a worker starts it.
+    token: Option<CancellationToken>,
+    /// The job's place in the start order, set when a worker takes it under
+    /// `Dispatch::FifoStart`.
+    ticket: Option<u64>,
+}
+
+impl QueuedJob {
+    fn new(job: Job) -> QueuedJob {
+        QueuedJob {
+            job,
+            name: None,
+            token: None,
+            ticket: None,
+        }
+    }
+}
+
+/// Formats a job's name for log messages, as ` 'name'` or nothing.
+struct JobLabel<'a>(&'a Option<String>);
+
+impl fmt::Display for JobLabel<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(name) => write!(f, " '{}'", name),
+            None => Ok(()),
+        }
+    }
+}
+
+enum Message {
+    NewJob(QueuedJob),
+    /// Tells one worker to exit after reporting its id on the sender.
+    Retire(mpsc::Sender<usize>),
+    /// Runs a broadcast closure. Only ever sent to one worker's inbox.
+    Broadcast(Delivery),
+}
+
+/// State shared between the pool and its workers.
+struct Shared {
+    queue: JobQueue,
+    config: Config,
+    rate_limiter: Option<RateLimiter>,
+    /// Set only when the pool has a watchdog.
+    running: Option<RunningJobs>,
+    limits: HashMap<SemaphoreKey, Arc<Semaphore>>,
+    /// Set only when the pool has a maximum concurrent weight.
+    weights: Option<Arc<Semaphore>>,
+    /// Set only when the pool spills tasks to disk.
+    spill: Option<Spill>,
+    active: AtomicUsize,
+    /// Jobs that are queued but not yet picked up by a worker.
+    queued: AtomicUsize,
+    /// Jobs that are queued or running.
+    pending: AtomicUsize,
+    submitted: AtomicU64,
+    completed: AtomicU64,
+    panicked: AtomicU64,
+    /// Jobs discarded by the `overflow_policy` of a full queue.
+    dropped: AtomicU64,
+    peak_active: AtomicUsize,
+    idle_lock: Mutex<()>,
+    idle: Condvar,
+    /// Callers blocked in `execute_when_free`, so workers only take
+    /// `free_lock` when someone is waiting.
+    free_waiters: AtomicUsize,
+    free_lock: Mutex<()>,
+    free: Condvar,
+    /// Callers blocked in `wait_for_completions`, so workers only take
+    /// `completion_lock` when someone is waiting.
+    completion_waiters: AtomicUsize,
+    completion_lock: Mutex<()>,
+    completion: Condvar,
+    /// Ids of workers that exited after a job panicked.
+    dead: Mutex<Vec<usize>>,
+    /// Set once any worker stops without being asked to.
+    stopped_unexpectedly: AtomicBool,
+    workers: Mutex<Vec<Worker>>,
+    /// Workers sent a `Retire` that have not been joined yet. Only changed
+    /// while `workers` is locked.
+    retiring: AtomicUsize,
+    next_id: AtomicUsize,
+    /// Set by whichever shutdown path runs first, so the others are no-ops.
+    shutting_down: AtomicBool,
+    /// The `mpsc::Sender<E>` from the latest call to `errors`, if any.
+    errors: Mutex<Option<Box<dyn Any + Send>>>,
+    /// Collects worker init results while the pool is being built.
+    init_report: Mutex<Option<mpsc::Sender<bool>>>,
+    /// One permit per queue slot, for `execute_async` on a bounded queue.
+    #[cfg(feature = "tokio")]
+    permits: Option<Arc<tokio::sync::Semaphore>>,
+}
+
+impl Shared {
+    fn job_submitted(&self) {
+        self.pending.fetch_add(1, Ordering::SeqCst);
+        self.queued.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn job_finished(&self) {
+        if self.pending.fetch_sub(1, Ordering::SeqCst) == 1 {
+            let _guard = lock(&self.idle_lock);
+            self.idle.notify_all();
+        }
+    }
+
+    /// Wakes callers of `execute_when_free` after a worker may have become
+    /// free.
+    fn worker_freed(&self) {
+        if self.free_waiters.load(Ordering::SeqCst) > 0 {
+            let _guard = lock(&self.free_lock);
+            self.free.notify_all();
+        }
+    }
+
+    /// Wakes callers of `wait_for_completions` after a job has completed.
+    fn job_completed(&self) {
+        self.completed.fetch_add(1, Ordering::SeqCst);
+
+        if self.completion_waiters.load(Ordering::SeqCst) > 0 {
+            let _guard = lock(&self.completion_lock);
+            self.completion.notify_all();
+        }
+    }
+
+    /// Returns whether some worker has neither a job running nor one queued
+    /// for it.
+    fn has_free_worker(&self) -> bool {
+        self.active.load(Ordering::SeqCst) + self.queued.load(Ordering::SeqCst)
+            < self.alive_workers()
+    }
+
+    /// Returns the number of workers that have not exited.
+    fn alive_workers(&self) -> usize {
+        lock(&self.workers).iter().filter(|w| w.is_alive()).count()
+    }
+
+    /// Returns the number of workers that have not exited and are not about
+    /// to, having been sent a `Retire`.
+    fn staying_workers(&self) -> usize {
+        let workers = lock(&self.workers);
+        let alive = workers.iter().filter(|w| w.is_alive()).count();
+
+        alive.saturating_sub(self.retiring.load(Ordering::SeqCst))
+    }
+
+    fn submit(self: &Arc<Self>, priority: u8, job: QueuedJob) -> Result<(), PushError> {
+        if self.config.immediate {
+            return self.run_inline(job);
+        }
+        if self.is_dead() {
+            return Err(PushError::Dead);
+        }
+
+        self.job_submitted();
+
+        // A worker blocked waiting for room might be the one that would have
+        // made it, so jobs submitted from inside the pool skip the capacity
+        // limit instead.
+        let message = Message::NewJob(job);
+        let result = if self.on_worker() {
+            self.queue.push_unbounded(priority, message)
+        } else {
+            self.push_bounded(priority, message)
+        };
+        self.job_pushed(&result);
+        self.grow();
+
+        match result {
+            Err(PushError::Full) if self.config.overflow_policy == OverflowPolicy::DropNewest => {
+                self.dropped.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    /// Pushes a job submitted from outside the pool, handling a full queue as
+    /// set with `overflow_policy`.
+    fn push_bounded(&self, priority: u8, message: Message) -> Result<(), PushError> {
+        match self.config.overflow_policy {
+            OverflowPolicy::Block => self.queue.push(priority, message),
+            OverflowPolicy::DropNewest | OverflowPolicy::Error => {
+                self.queue.try_push(priority, message)
+            }
+            OverflowPolicy::DropOldest => self.queue.push_evicting(priority, message, |_| {
+                self.queued.fetch_sub(1, Ordering::SeqCst);
+                self.dropped.fetch_add(1, Ordering::SeqCst);
+                self.job_finished();
+            }),
+        }
+    }
+
+    /// Submits a job for worker `id` alone to run.
+    fn submit_to(self: &Arc<Self>, id: usize, job: QueuedJob) -> Result<(), PushError> {
+        if self.config.immediate && id == 0 {
+            return self.run_inline(job);
+        }
+        if self.is_dead() {
+            return Err(PushError::Dead);
+        }
+
+        self.job_submitted();
+
+        let result = self.queue.push_to(id, Message::NewJob(job));
+        self.job_pushed(&result);
+
+        result
+    }
+
+    fn try_submit(self: &Arc<Self>, priority: u8, job: QueuedJob) -> Result<(), PushError> {
+        if self.config.immediate {
+            return self.run_inline(job);
+        }
+        if self.is_dead() {
+            return Err(PushError::Dead);
+        }
+
+        self.job_submitted();
+
+        let result = self.queue.try_push(priority, Message::NewJob(job));
+        self.job_pushed(&result);
+        self.grow();
+
+        result
+    }
+
+    fn submit_all(self: &Arc<Self>, jobs: Vec<QueuedJob>) -> Result<(), PushError> {
+        // A batch cannot wait for room as a whole, so a bounded queue takes
+        // it one job at a time.
+        if self.config.queue_capacity.is_some() || self.config.immediate {
+            return jobs.into_iter().try_for_each(|job| self.submit(0, job));
+        }
+
+        if self.is_dead() {
+            return Err(PushError::Dead);
+        }
+
+        let count = jobs.len();
+
+        self.pending.fetch_add(count, Ordering::SeqCst);
+        self.queued.fetch_add(count, Ordering::SeqCst);
+
+        let messages = jobs.into_iter().map(Message::NewJob).collect();
+        let result = self.queue.push_all(messages);
+
+        for _ in 0..count {
+            self.job_pushed(&result);
+        }
+        self.grow();
+
+        result
+    }
+
+    /// Runs `job` on the calling thread, for pools built in immediate mode.
+    ///
+    /// The job is reported as running on worker 0.
+    fn run_inline(&self, job: QueuedJob) -> Result<(), PushError> {
+        if self.queue.is_closed() {
+            return Err(PushError::Closed);
+        }
+
+        self.job_submitted();
+        self.job_pushed(&Ok(()));
+
+        // A panic is caught and logged, as `PanicPolicy::Continue` would.
+        let _ = run_job(0, self, job, None);
+        self.job_finished();
+
+        Ok(())
+    }
+
+    /// Turns a registered task into a job.
+    fn task_job(&self, task: TaskId, payload: Vec<u8>) -> Job {
+        let handler = Arc::clone(&self.config.task_handlers[&task].0);
+
+        Box::new(move || handler(payload))
+    }
+
+    /// Moves spilled tasks back into the queue while it is below the spill
+    /// threshold.
+    fn refill(self: &Arc<Self>) {
+        let Some(spill) = self.spill.as_ref().filter(|spill| spill.len() > 0) else {
+            return;
+        };
+
+        let _reload = spill.lock_reload();
+        self.reload(spill, spill.threshold());
+    }
+
+    /// Closes the queue, first moving every spilled task back into it so
+    /// that none is left behind on disk.
+    fn close_queue(self: &Arc<Self>) {
+        let Some(spill) = &self.spill else {
+            self.queue.close();
+            return;
+        };
+
+        let _reload = spill.lock_reload();
+        self.reload(spill, usize::MAX);
+        self.queue.close();
+    }
+
+    /// Moves tasks from `spill` back into the queue while fewer than `limit`
+    /// jobs are queued. The caller holds `spill.lock_reload()`.
+    fn reload(self: &Arc<Self>, spill: &Spill, limit: usize) {
+        while spill.len() > 0 && self.queued.load(Ordering::SeqCst) < limit {
+            match spill.pop() {
+                Ok(Some((task, payload))) => {
+                    // Spilled tasks were accepted already, so they skip the
+                    // capacity limit. Queued before the spilled task's own
+                    // count is released, so `wait_for_idle` cannot see zero
+                    // in between. A task loaded after shutdown is dropped.
+                    let message = Message::NewJob(QueuedJob::new(self.task_job(task, payload)));
+                    self.job_submitted();
+                    let result = self.queue.push_unbounded(0, message);
+                    self.job_pushed(&result);
+                    self.job_finished();
+                    self.grow();
+                }
+                Ok(None) => break,
+                Err(err) => {
+                    let lost = spill.clear();
+                    error!(
+                        "Dropping {} spilled task(s) that could not be read back: {}",
+                        lost, err
+                    );
+
+                    for _ in 0..lost {
+                        self.job_finished();
+                    }
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Returns whether every worker has died without being asked to stop,
+    /// leaving nothing to run queued jobs.
+    fn is_dead(&self) -> bool {
+        // Elastic pools spawn a fresh worker for the job instead.
+        if !self.stopped_unexpectedly.load(Ordering::SeqCst) || self.config.max_size.is_some() {
+            return false;
+        }
+
+        !lock(&self.workers).iter().any(Worker::is_alive)
+    }
+
+    /// Returns whether the calling thread is one of this pool's workers.
+    fn on_worker(&self) -> bool {
+        CURRENT_POOL.with(|pool| ptr::eq(pool.get(), self))
+    }
+
+    /// Puts `workers` in the order set with `shutdown_order`, if any.
+    fn sort_for_shutdown(&self, workers: &mut [Worker]) {
+        match self.config.shutdown_order {
+            Some(ShutdownOrder::Fifo) => workers.sort_by_key(|worker| worker.id),
+            Some(ShutdownOrder::Lifo) => {
+                workers.sort_by_key(|worker| cmp::Reverse(worker.id));
+            }
+            None => {}
+        }
+    }
+
+    /// Spawns a worker with the next free id.
+    fn spawn_worker(self: &Arc<Self>, workers: &mut Vec<Worker>) {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+
+        workers.push(Worker::new(id, Arc::clone(self)));
+    }
+
+    /// Queues `n` `Retire` messages, answered on `sender`, unless that would
+    /// leave fewer than `min_size` workers, or none, once every retiring
+    /// worker is gone.
+    ///
+    /// Takes the locked worker list so no idle worker exits between the
+    /// check and the messages being queued.
+    fn retire(
+        &self,
+        workers: &[Worker],
+        n: usize,
+        sender: &mpsc::Sender<usize>,
+    ) -> Result<(), ResizeError> {
+        let alive = workers.iter().filter(|w| w.is_alive()).count();
+        let removable = alive
+            .saturating_sub(self.retiring.load(Ordering::SeqCst))
+            .saturating_sub(self.config.min_size.max(1));
+
+        if n > removable {
+            return Err(ResizeError::TooFewWorkers { removable });
+        }
+
+        for _ in 0..n {
+            self.queue.push_control(Message::Retire(sender.clone()));
+        }
+        self.retiring.fetch_add(n, Ordering::SeqCst);
+
+        Ok(())
+    }
+
+    /// Removes worker `id`, which has answered a `Retire`, and joins it.
+    fn join_retired(&self, id: usize) {
+        let mut workers = lock(&self.workers);
+        self.retiring.fetch_sub(1, Ordering::SeqCst);
+
+        // A worker that is no longer listed has been joined already.
+        let Some(index) = workers.iter().position(|w| w.id == id) else {
+            return;
+        };
+        let worker = workers.swap_remove(index);
+        drop(workers);
+
+        debug!("Shutting down worker {}", worker.id);
+        worker.join();
+    }
+
+    /// Spawns an extra worker if the pool is elastic, there are more queued
+    /// jobs than workers waiting for one and the pool is below its maximum
+    /// size.
+    fn grow(self: &Arc<Self>) {
+        let Some(max_size) = self.config.max_size else {
+            return;
+        };
+        if self.queued.load(Ordering::SeqCst) <= self.queue.sleepers() {
+            return;
+        }
+
+        let mut workers = lock(&self.workers);
+
+        // Checked under the lock so no worker is spawned after shutdown has
+        // taken the list.
+        if self.queue.is_closed() {
+            return;
+        }
+
+        workers.retain(Worker::is_alive);
+
+        if workers.len() < max_size {
+            debug!("Growing the pool to {} workers.", workers.len() + 1);
+            self.spawn_worker(&mut workers);
+        }
+    }
+
+    /// Lets an idle worker exit unless that would take the pool below its
+    /// minimum size, marking it gone if so.
+    fn retire_idle(&self, slot: &ThreadSlot) -> bool {
+        let workers = lock(&self.workers);
+        let alive = workers.iter().filter(|w| w.is_alive()).count();
+
+        // A queued message may be a `Retire` that `remove_workers` is
+        // counting on this worker to answer.
+        if alive <= self.config.min_size || !self.queue.is_empty() {
+            return false;
+        }
+
+        lock(slot).take();
+
+        true
+    }
+
+    /// Removes every job still waiting in the queue.
+    fn take_queued(&self) -> Vec<Job> {
+        let jobs: Vec<Job> = self
+            .queue
+            .drain_jobs()
+            .into_iter()
+            .filter_map(|message| match message {
+                Message::NewJob(job) => Some(job.job),
+                Message::Retire(_) | Message::Broadcast(_) => None,
+            })
+            .collect();
+
+        for _ in &jobs {
+            self.queued.fetch_sub(1, Ordering::SeqCst);
+            self.job_finished();
+        }
+
+        jobs
+    }
+
+    fn job_pushed(&self, result: &Result<(), PushError>) {
+        match result {
+            Ok(()) => {
+                self.submitted.fetch_add(1, Ordering::SeqCst);
+                self.emit(PoolEvent::JobEnqueued);
+            }
+            Err(_) => {
+                self.queued.fetch_sub(1, Ordering::SeqCst);
+                self.job_finished();
+            }
+        }
+    }
+
+    fn emit(&self, event: PoolEvent) {
+        if let Some(Callback(callback)) = &self.config.on_event {
+            if panic::catch_unwind(AssertUnwindSafe(|| callback(event))).is_err() {
+                warn!("Pool event callback panicked.");
+            }
+        }
+    }
+}
+
+pub struct ThreadPool {
+    handle: ThreadPoolHandle,
+    watchdog: Option<Watchdog>,
+    starvation: Option<StarvationMonitor>,
+    scaler: Option<AutoScaler>,
+    timer: OnceLock<Timer>,
+    /// Workers to spawn on `restart`, counted when the pool shuts down.
+    restart_size: usize,
+}
+
+impl ThreadPool {
+    /// Create a new ThreadPool.
+    ///
+    /// The size is the number of threads in the pool. A pool of size 1 runs
+    /// jobs submitted with `execute` one at a time, in submission order.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PoolCreationError::ZeroSize` if the size is zero.
+    pub fn new(size: usize) -> Result<ThreadPool, PoolCreationError> {
+        ThreadPoolBuilder::new().size(size).build()
+    }
+
+    /// Create a new ThreadPool with one thread per logical CPU core.
+    ///
+    /// Falls back to a single thread if the core count cannot be determined.
+    pub fn with_num_cpus() -> ThreadPool {
+        let size = thread::available_parallelism().map_or(1, |n| n.get());
+
+        ThreadPool::with_config(size, Config::default())
+            .expect("a pool without worker_init cannot fail to start")
+    }
+
+    /// Create a new ThreadPool without worker threads, which runs each job
+    /// on the calling thread before `execute` returns.
+    ///
+    /// Jobs therefore run one at a time, in the order they are submitted,
+    /// which makes code built on the pool deterministic under test. See
+    /// `ThreadPoolBuilder::immediate`.
+    pub fn immediate() -> ThreadPool {
+        ThreadPoolBuilder::new()
+            .immediate(true)
+            .build()
+            .expect("a pool without worker_init cannot fail to start")
+    }
+
+    /// Create a new ThreadPool that also runs every job received on
+    /// `receiver`.
+    ///
+    /// This lets the pool be fed from a channel the caller already owns. A
+    /// forwarding thread moves each job from `receiver` into the pool's own
+    /// queue, so those jobs are scheduled like ones passed to `execute`. The
+    /// channel has to uphold a few things:
+    ///
+    /// - Jobs are run in no particular order relative to each other or to
+    ///   jobs submitted directly.
+    /// - `wait_for_idle` and `queued_count` only see a job once it has been
+    ///   forwarded.
+    /// - The forwarding thread exits when every sender has been dropped, or
+    ///   when a job arrives after the pool has shut down. Such late jobs are
+    ///   dropped without running.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PoolCreationError::ZeroSize` if the size is zero.
+    pub fn from_channel(
+        size: usize,
+        receiver: mpsc::Receiver<Box<dyn FnOnce() + Send + 'static>>,
+    ) -> Result<ThreadPool, PoolCreationError> {
+        let pool = ThreadPool::new(size)?;
+        let shared = Arc::clone(&pool.handle.shared);
+
+        thread::Builder::new()
+            .name("yarws-feeder".to_string())
+            .spawn(move || {
+                for job in receiver {
+                    if shared.submit(0, QueuedJob::new(job)).is_err() {
+                        break;
+                    }
+                }
+
+                debug!("Channel feeder exiting.");
+            })
+            .expect("failed to spawn feeder thread");
+
+        Ok(pool)
+    }
+
+    fn with_config(size: usize, mut config: Config) -> Result<ThreadPool, PoolCreationError> {
+        if config.suppress_panic_output {
+            install_quiet_panic_hook();
+        }
+
+        let shared = Arc::new(Shared {
+            queue: JobQueue::new(
+                config.queue_capacity,
+                config.dispatch,
+                config.custom_queue.take(),
+                config.shutdown_order.is_some(),
+            ),
+            rate_limiter: config.max_jobs_per_second.map(RateLimiter::new),
+            running: config.job_timeout_warning.map(|_| RunningJobs::default()),
+            limits: config
+                .concurrency_limits
+                .iter()
+                .map(|(key, &limit)| (key.clone(), Arc::new(Semaphore::new(limit))))
+                .collect(),
+            weights: config
+                .max_concurrent_weight
+                .map(|max| Arc::new(Semaphore::new(max as usize))),
+            spill: config
+                .spill
+                .as_ref()
+                .map(|(threshold, dir)| Spill::new(*threshold, dir)),
+            #[cfg(feature = "tokio")]
+            permits: config
+                .queue_capacity
+                .map(|capacity| Arc::new(tokio::sync::Semaphore::new(capacity))),
+            config,
+            active: AtomicUsize::new(0),
+            queued: AtomicUsize::new(0),
+            pending: AtomicUsize::new(0),
+            submitted: AtomicU64::new(0),
+            completed: AtomicU64::new(0),
+            panicked: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            peak_active: AtomicUsize::new(0),
+            idle_lock: Mutex::new(()),
+            idle: Condvar::new(),
+            free_waiters: AtomicUsize::new(0),
+            free_lock: Mutex::new(()),
+            free: Condvar::new(),
+            completion_waiters: AtomicUsize::new(0),
+            completion_lock: Mutex::new(()),
+            completion: Condvar::new(),
+            dead: Mutex::new(Vec::new()),
+            stopped_unexpectedly: AtomicBool::new(false),
+            workers: Mutex::new(Vec::with_capacity(size)),
+            retiring: AtomicUsize::new(0),
+            next_id: AtomicUsize::new(0),
+            shutting_down: AtomicBool::new(false),
+            errors: Mutex::new(None),
+            init_report: Mutex::new(None),
+        });
+
+        let mut pool = ThreadPool {
+            handle: ThreadPoolHandle { shared },
+            watchdog: None,
+            starvation: None,
+            scaler: None,
+            timer: OnceLock::new(),
+            restart_size: size,
+        };
+        pool.start(size)?;
+
+        Ok(pool)
+    }
+
+    /// Spawns the monitor threads and `size` workers, waiting for the workers
+    /// to finish `worker_init` if the pool has one.
+    fn start(&mut self, size: usize) -> Result<(), PoolCreationError> {
+        let shared = Arc::clone(&self.handle.shared);
+
+        self.watchdog = shared
+            .config
+            .job_timeout_warning
+            .map(|threshold| Watchdog::new(Arc::clone(&shared), threshold));
+        self.starvation = shared
+            .config
+            .starvation_check
+            .map(|interval| StarvationMonitor::new(Arc::clone(&shared), interval));
+        self.scaler = shared
+            .config
+            .auto_scale
+            .map(|interval| AutoScaler::new(Arc::clone(&shared), interval));
+
+        if shared.config.worker_init.is_none() {
+            self.add_workers(size);
+            return Ok(());
+        }
+
+        let (init_sender, init_receiver) = mpsc::channel();
+        *lock(&shared.init_report) = Some(init_sender);
+
+        self.add_workers(size);
+
+        let succeeded = init_receiver.iter().take(size).all(|ok| ok);
+        lock(&shared.init_report).take();
+
+        if !succeeded {
+            return Err(PoolCreationError::InitPanicked);
+        }
+
+        Ok(())
+    }
+
+    /// Spawns `n` more workers, which start taking jobs from the queue
+    /// straight away.
+    pub fn add_workers(&mut self, n: usize) {
+        let shared = &self.handle.shared;
+        let mut workers = lock(&shared.workers);
+
+        for _ in 0..n {
+            shared.spawn_worker(&mut workers);
+        }
+        drop(workers);
+
+        shared.worker_freed();
+    }
+
+    /// Stops and joins `n` workers.
+    ///
+    /// Whichever workers become free first are the ones removed, so this
+    /// blocks until `n` workers have finished their current job. Workers are
+    /// told to stop ahead of any queued job, and even while the pool is
+    /// paused, so the queue does not have to drain first.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ResizeError::TooFewWorkers` without removing anything unless
+    /// at least `min_size` workers, and at least one, would be left. Workers
+    /// that `auto_scale` is already removing do not count as left.
+    pub fn remove_workers(&mut self, n: usize) -> Result<(), ResizeError> {
+        if n == 0 {
+            return Ok(());
+        }
+
+        let shared = &self.handle.shared;
+        let (sender, receiver) = mpsc::channel();
+
+        shared.retire(&lock(&shared.workers), n, &sender)?;
+
+        for id in receiver.iter().take(n) {
+            shared.join_retired(id);
+        }
+
+        Ok(())
+    }
+
+    /// Grows or shrinks the pool to exactly `new_size` workers.
+    ///
+    /// Shrinking waits for the removed workers to finish their current job,
+    /// as `remove_workers` does. Workers that `auto_scale` is already
+    /// removing are not counted.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PoolCreationError::ZeroSize` without changing anything if
+    /// `new_size` is zero, or `PoolCreationError::SizeBounds` if it is below
+    /// `min_size` or, for an elastic or auto-scaled pool, above `max_size`.
+    pub fn set_size(&mut self, new_size: usize) -> Result<(), PoolCreationError> {
+        if new_size == 0 {
+            return Err(PoolCreationError::ZeroSize);
+        }
+
+        let config = &self.handle.shared.config;
+        let max_size = config
+            .max_size
+            .or(config.auto_scale.map(|_| config.scale_max));
+        if new_size < config.min_size || max_size.is_some_and(|max| new_size > max) {
+            return Err(PoolCreationError::SizeBounds);
+        }
+
+        // Workers can exit on their own in the meantime, in which case
+        // removing fails and the size is read again.
+        loop {
+            let size = self.handle.shared.staying_workers();
+
+            match new_size.cmp(&size) {
+                cmp::Ordering::Greater => self.add_workers(new_size - size),
+                cmp::Ordering::Less if self.remove_workers(size - new_size).is_err() => continue,
+                _ => {}
+            }
+
+            return Ok(());
+        }
+    }
+
+    /// Returns a cheap, cloneable handle for submitting jobs to this pool.
+    ///
+    /// Handles do not keep the pool alive: once the `ThreadPool` itself is
+    /// dropped or shut down, submitting through a handle fails as described
+    /// on `execute` and `try_execute`.
+    pub fn handle(&self) -> ThreadPoolHandle {
+        self.handle.clone()
+    }
+
+    /// Returns a cloneable `JobSender`, which can submit jobs to this pool
+    /// but do nothing else with it.
+    pub fn sender(&self) -> JobSender {
+        self.handle.sender()
+    }
+
+    /// Returns the number of threads in the pool.
+    ///
+    /// Workers that exited after a panic under `PanicPolicy::Propagate` are
+    /// not counted.
+    pub fn size(&self) -> usize {
+        self.handle.shared.alive_workers()
+    }
+
+    /// Returns a snapshot of every worker in the pool, in no particular
+    /// order.
+    ///
+    /// Workers whose thread has already been detached, such as ones that
+    /// exited after a panic under `PanicPolicy::Propagate`, are not listed.
+    pub fn worker_info(&self) -> Vec<WorkerInfo> {
+        let workers = lock(&self.handle.shared.workers);
+
+        workers.iter().filter_map(Worker::info).collect()
+    }
+
+    /// Returns, for each live worker, the name of the job it is running.
+    ///
+    /// Workers that are idle or running an unnamed job report `None`. Only
+    /// jobs submitted with `execute_named` have a name. Workers are listed in
+    /// the same order as in `worker_info`.
+    pub fn current_jobs(&self) -> Vec<Option<String>> {
+        let workers = lock(&self.handle.shared.workers);
+
+        workers
+            .iter()
+            .filter(|worker| worker.is_alive())
+            .map(|worker| lock(&worker.state.current).clone())
+            .collect()
+    }
+
+    /// Returns, for each live worker, the fraction of its lifetime it has
+    /// spent running jobs, from 0.0 to 1.0.
+    ///
+    /// A job counts once it finishes, so a worker busy with a long job reads
+    /// low until then. Uneven values across workers point at jobs being
+    /// dispatched unevenly. Workers are listed in the same order as in
+    /// `worker_info`.
+    pub fn utilization(&self) -> Vec<f64> {
+        let workers = lock(&self.handle.shared.workers);
+
+        workers
+            .iter()
+            .filter(|worker| worker.is_alive())
+            .map(Worker::utilization)
+            .collect()
+    }
+
+    /// Returns the number of workers currently executing a job.
+    pub fn active_count(&self) -> usize {
+        self.handle.active_count()
+    }
+
+    /// Returns the number of jobs waiting in the queue for a worker.
+    ///
+    /// Delayed jobs are only counted once they are due.
+    pub fn queued_count(&self) -> usize {
+        self.handle.queued_count()
+    }
+
+    /// Returns how many jobs have been discarded because the queue was full,
+    /// as set with `ThreadPoolBuilder::overflow_policy`.
+    pub fn dropped_count(&self) -> u64 {
+        self.handle.dropped_count()
+    }
+
+    /// Returns the pool's lifetime job counters.
+    ///
+    /// Each counter is read separately, so a snapshot taken while jobs are
+    /// running may be slightly inconsistent.
+    pub fn stats(&self) -> PoolStats {
+        self.handle.stats()
+    }
+
+    /// Returns whether every worker that has stopped was asked to.
+    ///
+    /// This turns `false` for good once a worker dies because a job panicked
+    /// under `PanicPolicy::Propagate`, or because `worker_init` panicked on a
+    /// worker started after the pool was built. Workers removed through
+    /// `remove_workers`, an idle timeout or `PanicPolicy::Respawn` do not
+    /// count. A supervisor can poll this to replace a degraded pool.
+    pub fn is_healthy(&self) -> bool {
+        self.handle.is_healthy()
+    }
+
+    /// Stops workers from picking up jobs until `resume` is called.
+    ///
+    /// Jobs that are already running finish normally, and new jobs are still
+    /// accepted and wait in the queue. While the pool is paused,
+    /// `wait_for_idle` blocks until it is resumed.
+    /// Shutting the pool down lifts the pause. This has no effect on a pool
+    /// in immediate mode.
+    pub fn pause(&self) {
+        self.handle.pause();
+    }
+
+    /// Lets workers pick up jobs again after `pause`.
+    pub fn resume(&self) {
+        self.handle.resume();
+    }
+
+    /// Returns whether the pool is paused.
+    pub fn is_paused(&self) -> bool {
+        self.handle.is_paused()
+    }
+
+    /// Blocks until every job submitted so far has finished.
+    pub fn wait_for_idle(&self) {
+        self.handle.wait_for_idle();
+    }
+
+    /// Blocks until `n` more jobs have completed, counting from the call.
+    ///
+    /// Jobs that panicked count as completed, as in `stats`. This is a
+    /// cheaper barrier than a handle per job when the size of a batch is
+    /// known, but it does not tell jobs apart: any `n` jobs will do. If fewer
+    /// than `n` more jobs ever complete, this blocks forever.
+    pub fn wait_for_completions(&self, n: usize) {
+        self.handle.wait_for_completions(n);
+    }
+
+    /// Drops every job still waiting in the queue without running it, and
+    /// returns how many there were.
+    ///
+    /// Jobs that are already running are not affected, and tasks spilled to
+    /// disk are discarded along with the rest. Delayed jobs that are not yet
+    /// due stay scheduled.
+    pub fn clear_queue(&self) -> usize {
+        self.handle.clear_queue()
+    }
+
+    /// Runs `f` on the pool.
+    ///
+    /// The job has priority 0, the lowest; see `execute_with_priority`.
+    ///
+    /// If the pool was built with a queue capacity and the queue is full, this
+    /// blocks until there is room, unless it is called from a job running on
+    /// this pool.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the job queue has been closed because the pool is shutting
+    /// down, or if every worker has died as described on `try_execute`. Use
+    /// `try_execute` to handle those cases instead.
+    pub fn execute<F>(&self, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.handle.execute(f);
+    }
+
+    /// Runs an already boxed job on the pool without boxing it again.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the job queue has been closed because the pool is shutting
+    /// down.
+    pub fn execute_boxed(&self, job: Box<dyn FnOnce() + Send + 'static>) {
+        self.handle.execute_boxed(job);
+    }
+
+    /// Runs `f` on the pool ahead of any queued jobs with a lower priority.
+    ///
+    /// Jobs with a priority above 0 and the same priority start in the order
+    /// they were submitted. Jobs with priority 0 are spread across the
+    /// workers' local queues, so their start order is only approximate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the job queue has been closed because the pool is shutting
+    /// down.
+    pub fn execute_with_priority<F>(&self, priority: u8, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.handle.execute_with_priority(priority, f);
+    }
+
+    /// Runs `f` on the pool, tagging it with `name` in log messages.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the job queue has been closed because the pool is shutting
+    /// down.
+    pub fn execute_named<F>(&self, name: impl Into<String>, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.handle.execute_named(name, f);
+    }
+
+    /// Handles the next queued message on the calling thread, for pools
+    /// built with `ThreadPoolBuilder::manual`, and returns whether there was
+    /// one.
+    ///
+    /// The calling thread acts as worker 0: it runs a job or broadcast as
+    /// that worker and answers a request to retire as it. A job that panics
+    /// is logged as under `PanicPolicy::Continue`. Only an empty queue makes
+    /// this return `false`.
+    #[cfg(feature = "manual")]
+    pub fn step(&self) -> bool {
+        self.handle.step()
+    }
+
+    /// Runs `f` on the pool, or on the calling thread if the pool is
+    /// overloaded.
+    ///
+    /// The pool counts as overloaded when no worker is free and more jobs are
+    /// queued than `ThreadPoolBuilder::inline_threshold` allows. Running the
+    /// job inline slows the producer down to the pool's pace instead of
+    /// letting the queue grow. A job run inline is not counted in `stats`,
+    /// and a panic in it propagates to the caller.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the job is queued and the job queue has been closed because
+    /// the pool is shutting down.
+    pub fn execute_or_run<F>(&self, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.handle.execute_or_run(f);
+    }
+
+    /// Runs `f` on the pool once a worker is free to start it straight away.
+    ///
+    /// If every worker is running or already has a job queued for it, this
+    /// blocks until one finishes, so jobs submitted this way never wait in
+    /// the queue. Called from a job running on this pool, or on a pool in
+    /// immediate mode, it submits `f` without waiting, since the caller may be
+    /// the worker it would wait for. A paused pool keeps the caller blocked
+    /// once the workers are taken.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the job queue has been closed because the pool is shutting
+    /// down, or if every worker has died as described on `try_execute`.
+    pub fn execute_when_free<F>(&self, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.handle.execute_when_free(f);
+    }
+
+    /// Runs the handler registered for `task` with
+    /// `ThreadPoolBuilder::task_handler` on the pool, passing it `payload`.
+    ///
+    /// Unlike a closure, a task can be written to disk while the queue is
+    /// long; see `ThreadPoolBuilder::spill_to_disk`. Tasks still on disk when
+    /// the pool shuts down are loaded back into the queue first, so they run
+    /// as part of a graceful shutdown or are returned by `shutdown_now`. If a
+    /// spilled task cannot be written, it is queued in memory instead.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no handler is registered for `task`, or if the job queue
+    /// has been closed because the pool is shutting down.
+    pub fn execute_task(&self, task: TaskId, payload: Vec<u8>) {
+        self.handle.execute_task(task, payload);
+    }
+
+    /// Runs `f` on the pool with a reference to the context set with
+    /// `ThreadPoolBuilder::context`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pool has no context of type `C`, or if the job queue
+    /// has been closed because the pool is shutting down.
+    pub fn execute_with_ctx<C, F>(&self, f: F)
+    where
+        C: Send + Sync + 'static,
+        F: FnOnce(&C) + Send + 'static,
+    {
+        self.handle.execute_with_ctx(f);
+    }
+
+    /// Returns the context set with `ThreadPoolBuilder::context`, or `None`
+    /// if there is none or it is not a `C`.
+    pub fn context<C>(&self) -> Option<Arc<C>>
+    where
+        C: Send + Sync + 'static,
+    {
+        self.handle.context()
+    }
+
+    /// Runs `f` on the pool unless `token` is cancelled before a worker
+    /// starts it.
+    ///
+    /// The returned receiver yields `true` once `f` has run, or `false` if
+    /// the job was skipped. If `f` panics, the receiver yields nothing and
+    /// reports a disconnect instead. Cancelling the token after `f` has
+    /// started does not stop it; `f` can check a clone of the token itself.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the job queue has been closed because the pool is shutting
+    /// down.
+    pub fn execute_cancellable<F>(&self, token: CancellationToken, f: F) -> mpsc::Receiver<bool>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.handle.execute_cancellable(token, f)
+    }
+
+    /// Runs `f` on the pool unless `deadline` passes before a worker starts
+    /// it.
+    ///
+    /// `f` is given a `DeadlineToken` to check while it runs; the job is
+    /// never stopped from outside. The returned receiver yields the
+    /// `DeadlineOutcome` once the job has run or been skipped. If `f`
+    /// panics, the receiver yields nothing and reports a disconnect instead.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the job queue has been closed because the pool is shutting
+    /// down.
+    pub fn execute_with_deadline<F>(
+        &self,
+        deadline: Instant,
+        f: F,
+    ) -> mpsc::Receiver<DeadlineOutcome>
+    where
+        F: FnOnce(&DeadlineToken) + Send + 'static,
+    {
+        self.handle.execute_with_deadline(deadline, f)
+    }
+
+    /// Runs `f` on the pool, waiting asynchronously while the queue is full.
+    ///
+    /// On a pool built with a queue capacity, this awaits a free slot instead
+    /// of blocking the calling thread, so it is safe to call from an async
+    /// runtime. Slots are shared with `execute`, so mixing the two on a full
+    /// queue can still block briefly. Requires the `tokio` feature.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the job queue has been closed because the pool is shutting
+    /// down.
+    #[cfg(feature = "tokio")]
+    pub async fn execute_async<F>(&self, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.handle.execute_async(f).await;
+    }
+
+    /// Runs `f` on the pool, counting it against the concurrency limit set
+    /// for `key` with `ThreadPoolBuilder::concurrency_limit`.
+    ///
+    /// The worker that picks the job up waits for a permit before running
+    /// `f` and releases it afterwards, even if `f` panics. If no limit was set
+    /// for `key`, a warning is logged and the job runs unlimited.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the job queue has been closed because the pool is shutting
+    /// down.
+    pub fn execute_limited<F>(&self, key: SemaphoreKey, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.handle.execute_limited(key, f);
+    }
+
+    /// Runs `f` on the pool, counting `weight` against the maximum set with
+    /// `ThreadPoolBuilder::max_concurrent_weight`.
+    ///
+    /// This models jobs with different costs: a heavy job can take up the
+    /// room of several light ones. The worker that picks the job up waits
+    /// until the total weight of running jobs leaves room for it, behind any
+    /// weighted job picked up earlier, and releases the weight afterwards,
+    /// even if `f` panics. If no maximum was
+    /// set, a warning is logged and the job runs unlimited.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `weight` is above the maximum, since the job could never
+    /// run, or if the job queue has been closed because the pool is shutting
+    /// down.
+    pub fn execute_weighted<F>(&self, weight: u32, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.handle.execute_weighted(weight, f);
+    }
+
+    /// Drains the pool when the process receives SIGTERM or SIGINT.
+    ///
+    /// On the first of these signals the pool stops accepting jobs, every job
+    /// already submitted runs to completion, and the signal then terminates
+    /// the process as it would have without a handler. This lets a service
+    /// shut down cleanly when its container is stopped. Submitting a job after
+    /// the signal panics, as it does once the pool is shutting down.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the signal handler or its thread cannot be set up.
+    #[cfg(feature = "signal")]
+    pub fn install_signal_handler(&self) -> std::io::Result<()> {
+        self.handle.install_signal_handler()
+    }
+
+    /// Runs `f` on the pool once `delay` has passed.
+    ///
+    /// Delayed jobs that are not yet due when the pool shuts down are dropped
+    /// without running. They are not counted by `wait_for_idle` until they
+    /// are due.
+    pub fn execute_after<F>(&self, delay: Duration, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let due = Instant::now() + delay;
+
+        self.timer().state().schedule(due, Box::new(f));
+    }
+
+    /// Runs `f` on the pool every `interval`, starting one interval from now.
+    ///
+    /// The job keeps recurring until the returned handle is cancelled or
+    /// dropped, or the pool shuts down. Runs are scheduled at a fixed rate, so
+    /// a run that overruns its interval is followed immediately by the next.
+    pub fn execute_every<F>(&self, interval: Duration, f: F) -> TaskHandle
+    where
+        F: FnMut() + Send + 'static,
+    {
+        timer::schedule_every(self.timer().state(), interval, f)
+    }
+
+    fn timer(&self) -> &Timer {
+        self.timer
+            .get_or_init(|| Timer::new(Arc::clone(&self.handle.shared)))
+    }
+
+    /// Runs every job from `jobs` on the pool.
+    ///
+    /// This is cheaper than calling `execute` in a loop: on an unbounded
+    /// queue the whole batch is enqueued in one go.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the job queue has been closed because the pool is shutting
+    /// down.
+    pub fn execute_all<I, F>(&self, jobs: I)
+    where
+        I: IntoIterator<Item = F>,
+        F: FnOnce() + Send + 'static,
+    {
+        self.handle.execute_all(jobs);
+    }
+
+    /// Applies `f` to every item on the pool and returns the results in the
+    /// same order as `items`.
+    ///
+    /// Blocks until every item has been processed. If `f` panics for any
+    /// item, the panic is resumed on the calling thread.
+    pub fn map<T, R, F>(&self, items: Vec<T>, f: F) -> Vec<R>
+    where
+        T: Send + 'static,
+        R: Send + 'static,
+        F: Fn(T) -> R + Send + Sync + 'static,
+    {
+        self.handle.map(items, f)
+    }
+
+    /// Applies `f` on the pool to every item received on `input`, sending
+    /// the results out on the returned receiver as they become ready.
+    ///
+    /// This makes the pool one stage of a streaming pipeline. A forwarding
+    /// thread submits a job for each item, so results come out in no
+    /// particular order. The output channel disconnects once `input` has
+    /// disconnected and every item has been processed. If `f` panics for an
+    /// item, that item produces no output. Items that arrive after the pool
+    /// has shut down are dropped, and the forwarding thread exits.
+    pub fn pipeline<T, R, F>(&self, input: mpsc::Receiver<T>, f: F) -> mpsc::Receiver<R>
+    where
+        T: Send + 'static,
+        R: Send + 'static,
+        F: Fn(T) -> R + Send + Sync + 'static,
+    {
+        self.handle.pipeline(input, f)
+    }
+
+    /// Runs `f` on the pool without blocking.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ExecuteError::Full` if the pool was built with a queue
+    /// capacity and the queue is full, `ExecuteError::ShutDown` if the job
+    /// queue has been closed, or `ExecuteError::PoolDead` if every worker of
+    /// a pool that is not elastic has died, so the job would never run.
+    pub fn try_execute<F>(&self, f: F) -> Result<(), ExecuteError>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.handle.try_execute(f)
+    }
+
+    /// Returns a `Collector` for running jobs on the pool and gathering
+    /// their results by index.
+    pub fn collector<T>(&self) -> Collector<T>
+    where
+        T: Send + 'static,
+    {
+        self.handle.collector()
+    }
+
+    /// Returns a new `JobGroup`, whose jobs can be cancelled together.
+    pub fn group(&self) -> JobGroup {
+        self.handle.group()
+    }
+
+    /// Runs `f` on the worker with id `worker_id`, and no other.
+    ///
+    /// Related jobs pinned to the same worker run one after another on the
+    /// same thread, so they can share thread-local caches. Worker ids are the
+    /// ones listed by `worker_info` and passed to `worker_init`. A pinned job
+    /// runs ahead of the worker's share of the queue, but not while the pool
+    /// is paused, and it does not count towards `queue_capacity`. If the
+    /// worker exits before running it, another worker runs it instead. An
+    /// immediate pool runs jobs pinned to worker 0 on the calling thread.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ExecuteError::NoSuchWorker` if no live worker has that id,
+    /// `ExecuteError::ShutDown` if the job queue has been closed, or
+    /// `ExecuteError::PoolDead` if every worker of a pool that is not elastic
+    /// has died.
+    pub fn execute_on<F>(&self, worker_id: usize, f: F) -> Result<(), ExecuteError>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.handle.execute_on(worker_id, f)
+    }
+
+    /// Runs `f` once on every worker, passing the worker's id, and blocks
+    /// until all of them have run it.
+    ///
+    /// This suits per-thread setup, such as warming caches, on a pool that is
+    /// already running. Each worker runs `f` as soon as it finishes its
+    /// current job, ahead of anything queued and even while the pool is
+    /// paused. Broadcasts are not counted as jobs in `stats`. A worker that
+    /// exits before its turn is skipped. An immediate pool runs `f(0)` on
+    /// the calling thread. If `f` panics on any worker, the first panic is
+    /// resumed here once every worker is done.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from a job on the same pool, since that worker could
+    /// never take its turn.
+    pub fn broadcast<F>(&self, f: F)
+    where
+        F: Fn(usize) + Send + Sync + 'static,
+    {
+        self.handle.broadcast(f);
+    }
+
+    /// Runs `f` on the pool and returns a receiver that yields its result.
+    ///
+    /// Call `recv()` on the receiver to block until the job has finished.
+    /// Dropping the receiver detaches the job: it still runs, and its result
+    /// is discarded without holding up the worker.
+    pub fn execute_with_result<F, T>(&self, f: F) -> mpsc::Receiver<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        self.handle.execute_with_result(f)
+    }
+
+    /// Runs `f` on the pool, sending the error it returns, if any, to the
+    /// receiver from `errors`.
+    ///
+    /// This gives one place to watch for failures across many jobs. If no
+    /// receiver for errors of type `E` is open, the error is logged instead.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the job queue has been closed because the pool is shutting
+    /// down.
+    pub fn execute_fallible<F, E>(&self, f: F)
+    where
+        F: FnOnce() -> Result<(), E> + Send + 'static,
+        E: fmt::Debug + Send + 'static,
+    {
+        self.handle.execute_fallible(f);
+    }
+
+    /// Returns a receiver for the errors of jobs submitted with
+    /// `execute_fallible`.
+    ///
+    /// Only one receiver is open at a time: calling this again replaces the
+    /// previous one, which is then disconnected. Errors of a type
+    /// other than `E` are logged instead. Once the receiver is dropped, later
+    /// errors are discarded.
+    pub fn errors<E>(&self) -> mpsc::Receiver<E>
+    where
+        E: Send + 'static,
+    {
+        self.handle.errors()
+    }
+
+    /// Runs `f` on the pool, running it again each time it returns an error,
+    /// up to `attempts` runs in total.
+    ///
+    /// Retries happen straight away on the same worker. Each failure is
+    /// logged, and the returned receiver yields `Ok(())` once a run succeeds
+    /// or the last error once every attempt has failed. Dropping the
+    /// receiver discards that outcome, as with `execute_with_result`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `attempts` is zero, or if the job queue has been closed
+    /// because the pool is shutting down.
+    pub fn execute_with_retry<F, E>(&self, attempts: u32, f: F) -> mpsc::Receiver<Result<(), E>>
+    where
+        F: Fn() -> Result<(), E> + Send + Sync + 'static,
+        E: fmt::Debug + Send + 'static,
+    {
+        self.handle.execute_with_retry(attempts, f)
+    }
+
+    /// Runs `f` on the pool and returns a handle for joining on its result.
+    ///
+    /// A panic in `f` is caught and reported by `JobHandle::join`.
+    pub fn execute_with_handle<F, T>(&self, f: F) -> JobHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        self.handle.execute_with_handle(f)
+    }
+
+    /// Runs the blocking function `f` on the pool, for code written against
+    /// `tokio::task::spawn_blocking`.
+    ///
+    /// This is `execute_with_handle` under the name and shape Tokio uses, so
+    /// `spawn_blocking(f).join()` returns what `f` returned.
+    pub fn spawn_blocking<F, R>(&self, f: F) -> BlockingHandle<R>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        self.handle.spawn_blocking(f)
+    }
+
+    /// Runs `f` once on the pool and hands it back, along with any state it
+    /// mutated, through the returned handle.
+    ///
+    /// This lets a stateful job be run again later by passing it back in. To
+    /// run one on a schedule, use `execute_every`, which keeps the same
+    /// closure across runs and never runs it concurrently with itself.
+    pub fn execute_mut<F>(&self, f: F) -> JobHandle<F>
+    where
+        F: FnMut() + Send + 'static,
+    {
+        self.handle.execute_mut(f)
+    }
+
+    /// Runs `a` on the pool and `b` on the calling thread, and returns both
+    /// results once both have finished.
+    ///
+    /// This is the divide-and-conquer primitive for recursive parallel
+    /// algorithms, and both closures may borrow from the caller. If no worker
+    /// has started `a` by the time `b` finishes, the calling thread runs `a`
+    /// itself, so calling this from inside a job cannot deadlock even on a
+    /// single-worker pool. If either closure panics, the panic is resumed on
+    /// the calling thread once both have finished.
+    pub fn fork_join<A, B, RA, RB>(&self, a: A, b: B) -> (RA, RB)
+    where
+        A: FnOnce() -> RA + Send,
+        B: FnOnce() -> RB,
+        RA: Send,
+    {
+        self.handle.fork_join(a, b)
+    }
+
+    /// Runs every task on the pool and returns their results in the order
+    /// the tasks were given.
+    ///
+    /// Blocks until every task has finished. The calling thread helps by
+    /// running any task that no worker has started yet, so calling this from
+    /// inside a job cannot deadlock even on a single-worker pool. If any task
+    /// panics, the panic is resumed on the calling thread once every task has
+    /// finished.
+    pub fn scatter_gather<F, R>(&self, tasks: Vec<F>) -> Vec<R>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        self.handle.scatter_gather(tasks)
+    }
+
+    /// Runs `f` on the pool and returns a future for its result.
+    ///
+    /// The future is woken when the job finishes, so it can be awaited from
+    /// any executor without blocking a thread. It resolves to
+    /// `Err(JobError::Panicked)` if `f` panics, and to
+    /// `Err(JobError::Dropped)` if the job is discarded before it runs.
+    pub fn execute_future<F, T>(&self, f: F) -> PoolFuture<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        self.handle.execute_future(f)
+    }
+
+    /// Runs `fut` to completion on the pool and returns a future for its
+    /// output.
+    ///
+    /// The worker drives `fut` with a minimal executor that parks the thread
+    /// while the future is pending, so the worker is occupied until `fut`
+    /// completes. The returned future can be awaited from any executor, and
+    /// resolves to an error as described on `execute_future`.
+    pub fn spawn<Fut>(&self, fut: Fut) -> PoolFuture<Fut::Output>
+    where
+        Fut: Future + Send + 'static,
+        Fut::Output: Send + 'static,
+    {
+        self.handle.spawn(fut)
+    }
+
+    /// Creates a scope in which jobs can borrow data from the caller's stack.
+    ///
+    /// `scope` does not return until every job spawned on the scope has
+    /// finished. If `f` or any of those jobs panicked, the panic is resumed
+    /// on the calling thread.
+    ///
+    /// Calling this from inside a job can deadlock if every worker ends up
+    /// waiting on a scope.
+    pub fn scope<'scope, F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&Scope<'_, 'scope>) -> R,
+    {
+        let scope = Scope::new(self);
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| f(&scope)));
+        let job_panic = scope.wait();
+
+        let value = match result {
+            Ok(value) => value,
+            Err(payload) => panic::resume_unwind(payload),
+        };
+        if let Some(payload) = job_panic {
+            panic::resume_unwind(payload);
+        }
+
+        value
+    }
+
+    /// Splits `data` into chunks of `chunk_size` elements and runs `f` on each
+    /// chunk in parallel, returning once every chunk has been processed.
+    ///
+    /// The last chunk may be shorter. Each job gets its own non-overlapping
+    /// sub-slice, so `f` is free to mutate it. If `f` panics for any chunk,
+    /// the panic is resumed on the calling thread.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub fn for_each_chunk<T, F>(&self, data: &mut [T], chunk_size: usize, f: F)
+    where
+        T: Send,
+        F: Fn(&mut [T]) + Send + Sync,
+    {
+        let f = &f;
+
+        self.scope(|scope| {
+            for chunk in data.chunks_mut(chunk_size) {
+                scope.execute(move || f(chunk));
+            }
+        });
+    }
+
+    /// Shuts the pool down, waiting at most `dur` for the workers to finish.
+    ///
+    /// Workers that are still running a job when the deadline passes are
+    /// detached: they exit on their own once their current job completes.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ShutdownError::Timeout` with the number of workers that were
+    /// still alive at the deadline.
+    pub fn shutdown_timeout(mut self, dur: Duration) -> Result<(), ShutdownError> {
+        let deadline = Instant::now() + dur;
+
+        info!("Shutting down all workers within {:?}.", dur);
+        self.begin_shutdown();
+
+        let mut workers = mem::take(&mut *lock(&self.handle.shared.workers));
+        self.handle.shared.sort_for_shutdown(&mut workers);
+
+        for worker in &workers {
+            self.handle.shared.queue.stop(worker.id);
+        }
+
+        loop {
+            workers.retain(|worker| {
+                let mut slot = lock(&worker.thread);
+                let finished = slot.as_ref().is_none_or(|t| t.is_finished());
+
+                if finished {
+                    if let Some(thread) = slot.take() {
+                        debug!("Shutting down worker {}", worker.id);
+
+                        if thread.join().is_err() {
+                            warn!("Worker {} panicked before shutting down.", worker.id);
+                        }
+                    }
+                }
+
+                !finished
+            });
+
+            if workers.is_empty() {
+                return Ok(());
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(ShutdownError::Timeout {
+                    remaining: workers.len(),
+                });
+            }
+
+            thread::sleep(cmp::min(deadline - now, SHUTDOWN_POLL_INTERVAL));
+        }
+    }
+
+    /// Shuts the pool down, waiting for every worker to finish, and reports
+    /// how it went.
+    ///
+    /// Jobs already in the queue still run first. Dropping the pool does the
+    /// same but discards the summary.
+    pub fn join(mut self) -> ShutdownSummary {
+        self.shut_down()
+    }
+
+    /// Shuts the pool down in place, waiting for every worker to finish, and
+    /// reports how it went.
+    ///
+    /// Like `join`, but the pool stays around and can be brought back with
+    /// `restart`. Until then, submitting a job panics.
+    pub fn shutdown(&mut self) -> ShutdownSummary {
+        self.shut_down()
+    }
+
+    /// Brings the pool back after `shutdown`, with fresh workers and an
+    /// emptied queue.
+    ///
+    /// The pool gets as many workers as were alive when it shut down, but at
+    /// least one, and keeps its configuration and statistics. Immediate and
+    /// manual pools stay without workers. Handles taken before the shutdown
+    /// submit to the restarted pool. A pool that is still running is shut
+    /// down first, which makes this a way to recycle every worker.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PoolCreationError::InitPanicked` if a worker's `worker_init`
+    /// callback panicked, in which case the pool is left shut down.
+    pub fn restart(&mut self) -> Result<(), PoolCreationError> {
+        let shared = Arc::clone(&self.handle.shared);
+
+        if !shared.shutting_down.load(Ordering::SeqCst) {
+            self.shut_down();
+        }
+
+        info!("Restarting the pool with {} workers.", self.restart_size);
+
+        shared.queue.reopen();
+        shared.retiring.store(0, Ordering::SeqCst);
+        shared.stopped_unexpectedly.store(false, Ordering::SeqCst);
+        shared.shutting_down.store(false, Ordering::SeqCst);
+
+        if let Err(e) = self.start(self.restart_size) {
+            self.shut_down();
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    /// Stops accepting new jobs and shuts the pool down once every job
+    /// already in the queue has run.
+    ///
+    /// This is what dropping the pool does too. Unlike `shutdown_now`, no
+    /// queued job is abandoned.
+    /// Delayed jobs that are not yet due are still dropped.
+    pub fn shutdown_draining(self) {
+        self.join();
+    }
+
+    /// Shuts the pool down without running the jobs still in the queue, and
+    /// returns them instead.
+    ///
+    /// Workers stop as soon as they finish the job they are running, and the
+    /// queued jobs are handed back in the order they would have started, so
+    /// the caller can run them elsewhere or inspect them. Delayed jobs that
+    /// are not yet due are dropped.
+    pub fn shutdown_now(mut self) -> Vec<Box<dyn FnOnce() + Send + 'static>> {
+        info!("Shutting down all workers now.");
+        self.begin_shutdown();
+
+        let jobs = self.handle.shared.take_queued();
+        debug!("Abandoned {} queued job(s).", jobs.len());
+
+        self.join_workers(0);
+
+        jobs
+    }
+
+    /// Stops the pool from accepting jobs and tells the workers to exit once
+    /// the queue is empty.
+    fn begin_shutdown(&mut self) {
+        if self
+            .handle
+            .shared
+            .shutting_down
+            .swap(true, Ordering::SeqCst)
+        {
+            return;
+        }
+
+        self.handle.shared.emit(PoolEvent::ShutdownStarted);
+        // Workers that died under `PanicPolicy::Propagate` are still listed
+        // until they are joined, but are not brought back. Immediate and
+        // manual pools never had any.
+        let config = &self.handle.shared.config;
+        self.restart_size = if config.immediate || config.manual {
+            0
+        } else {
+            self.handle.shared.alive_workers().max(1)
+        };
+
+        self.timer.take();
+        self.watchdog.take();
+        self.starvation.take();
+        self.scaler.take();
+        self.handle.shared.close_queue();
+        self.handle.shared.worker_freed();
+    }
+
+    fn shut_down(&mut self) -> ShutdownSummary {
+        let jobs_remaining_estimate = self.queued_count();
+
+        info!("Gracefully shutting down all workers.");
+        self.begin_shutdown();
+
+        self.join_workers(jobs_remaining_estimate)
+    }
+
+    fn join_workers(&mut self, jobs_remaining_estimate: usize) -> ShutdownSummary {
+        let mut workers_joined = 0;
+        let mut failed = Vec::new();
+
+        let mut workers = mem::take(&mut *lock(&self.handle.shared.workers));
+        self.handle.shared.sort_for_shutdown(&mut workers);
+
+        for worker in workers {
+            debug!("Shutting down worker {}", worker.id);
+            self.handle.shared.queue.stop(worker.id);
+
+            match worker.join() {
+                Some(true) => workers_joined += 1,
+                Some(false) => failed.push(worker.id),
+                None => {}
+            }
+        }
+
+        // Read only once every worker has exited, so no late deaths are
+        // missed.
+        let mut panicked_workers = mem::take(&mut *lock(&self.handle.shared.dead));
+        panicked_workers.extend(failed);
+
+        ShutdownSummary {
+            workers_joined,
+            jobs_remaining_estimate,
+            panicked_workers,
+        }
+    }
+}
+
+/// A cloneable handle for submitting jobs to a `ThreadPool`.
+///
+/// Every clone targets the same workers. Dropping a handle never shuts the
+/// pool down; only dropping the owning `ThreadPool` does.
+#[derive(Clone)]
+pub struct ThreadPoolHandle {
+    shared: Arc<Shared>,
+}
+
+impl ThreadPoolHandle {
+    /// See `ThreadPool::active_count`.
+    pub fn active_count(&self) -> usize {
+        self.shared.active.load(Ordering::SeqCst)
+    }
+
+    /// See `ThreadPool::queued_count`.
+    pub fn queued_count(&self) -> usize {
+        self.shared.queued.load(Ordering::SeqCst)
+    }
+
+    /// See `ThreadPool::dropped_count`.
+    pub fn dropped_count(&self) -> u64 {
+        self.shared.dropped.load(Ordering::SeqCst)
+    }
+
+    /// See `ThreadPool::stats`.
+    pub fn stats(&self) -> PoolStats {
+        PoolStats {
+            total_submitted: self.shared.submitted.load(Ordering::SeqCst),
+            total_completed: self.shared.completed.load(Ordering::SeqCst),
+            total_panicked: self.shared.panicked.load(Ordering::SeqCst),
+            peak_active: self.shared.peak_active.load(Ordering::SeqCst),
+        }
+    }
+
+    /// See `ThreadPool::is_healthy`.
+    pub fn is_healthy(&self) -> bool {
+        !self.shared.stopped_unexpectedly.load(Ordering::SeqCst)
+    }
+
+    /// See `ThreadPool::pause`.
+    pub fn pause(&self) {
+        debug!("Pausing the pool.");
+        self.shared.queue.pause();
+    }
+
+    /// See `ThreadPool::resume`.
+    pub fn resume(&self) {
+        debug!("Resuming the pool.");
+        self.shared.queue.resume();
+    }
+
+    /// See `ThreadPool::is_paused`.
+    pub fn is_paused(&self) -> bool {
+        self.shared.queue.is_paused()
+    }
+
+    /// See `ThreadPool::wait_for_idle`.
+    pub fn wait_for_idle(&self) {
+        let guard = lock(&self.shared.idle_lock);
+        let _guard = self
+            .shared
+            .idle
+            .wait_while(guard, |_| self.shared.pending.load(Ordering::SeqCst) > 0)
+            .unwrap_or_else(PoisonError::into_inner);
+    }
+
+    /// See `ThreadPool::wait_for_completions`.
+    pub fn wait_for_completions(&self, n: usize) {
+        let shared = &self.shared;
+
+        shared.completion_waiters.fetch_add(1, Ordering::SeqCst);
+
+        let guard = lock(&shared.completion_lock);
+        let target = shared.completed.load(Ordering::SeqCst) + n as u64;
+        let _guard = shared
+            .completion
+            .wait_while(guard, |_| shared.completed.load(Ordering::SeqCst) < target)
+            .unwrap_or_else(PoisonError::into_inner);
+
+        shared.completion_waiters.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    /// See `ThreadPool::clear_queue`.
+    pub fn clear_queue(&self) -> usize {
+        let shared = &self.shared;
+        let mut cleared = shared.take_queued().len();
+
+        if let Some(spill) = &shared.spill {
+            let spilled = spill.clear();
+
+            for _ in 0..spilled {
+                shared.job_finished();
+            }
+            cleared += spilled;
+        }
+        shared.worker_freed();
+
+        debug!("Cleared {} queued job(s).", cleared);
+
+        cleared
+    }
+
+    /// See `ThreadPool::execute`.
+    pub fn execute<F>(&self, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.execute_with_priority(0, f);
+    }
+
+    /// See `ThreadPool::execute_boxed`.
+    pub fn execute_boxed(&self, job: Box<dyn FnOnce() + Send + 'static>) {
+        expect_submitted(self.shared.submit(0, QueuedJob::new(job)));
+    }
+
+    /// See `ThreadPool::execute_with_priority`.
+    pub fn execute_with_priority<F>(&self, priority: u8, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        expect_submitted(self.shared.submit(priority, QueuedJob::new(Box::new(f))));
+    }
+
+    /// See `ThreadPool::execute_named`.
+    pub fn execute_named<F>(&self, name: impl Into<String>, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let job = QueuedJob {
+            name: Some(name.into()),
+            ..QueuedJob::new(Box::new(f))
+        };
+
+        expect_submitted(self.shared.submit(0, job));
+    }
+
+    /// See `ThreadPool::step`.
+    #[cfg(feature = "manual")]
+    pub fn step(&self) -> bool {
+        let Some(message) = self.shared.queue.try_pop() else {
+            return false;
+        };
+
+        // The calling thread stands in for worker 0, as it does for jobs.
+        match message {
+            Message::NewJob(job) => {
+                // A panic is caught and logged, as `PanicPolicy::Continue`
+                // would.
+                let _ = run_job(0, &self.shared, job, None);
+                self.shared.job_finished();
+            }
+            Message::Broadcast(delivery) => delivery.run(0),
+            Message::Retire(sender) => {
+                let _ = sender.send(0);
+            }
+        }
+
+        true
+    }
+
+    /// See `ThreadPool::execute_or_run`.
+    pub fn execute_or_run<F>(&self, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let shared = &self.shared;
+        let threshold = shared
+            .config
+            .inline_threshold
+            .unwrap_or_else(|| shared.alive_workers());
+
+        if shared.has_free_worker() || shared.queued.load(Ordering::SeqCst) <= threshold {
+            self.execute(f);
+            return;
+        }
+
+        trace!("Pool is overloaded; running a job on the calling thread.");
+        f();
+    }
+
+    /// See `ThreadPool::execute_when_free`.
+    pub fn execute_when_free<F>(&self, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let shared = &self.shared;
+
+        if shared.config.immediate || shared.on_worker() {
+            self.execute(f);
+            return;
+        }
+
+        shared.free_waiters.fetch_add(1, Ordering::SeqCst);
+
+        // Submitting under the lock keeps two waiters from both claiming the
+        // same free worker.
+        let guard = lock(&shared.free_lock);
+        let _guard = shared
+            .free
+            .wait_while(guard, |_| {
+                !shared.queue.is_closed() && !shared.is_dead() && !shared.has_free_worker()
+            })
+            .unwrap_or_else(PoisonError::into_inner);
+
+        shared.free_waiters.fetch_sub(1, Ordering::SeqCst);
+
+        expect_submitted(shared.submit(0, QueuedJob::new(Box::new(f))));
+    }
+
+    /// See `ThreadPool::execute_task`.
+    pub fn execute_task(&self, task: TaskId, payload: Vec<u8>) {
+        let shared = &self.shared;
+
+        assert!(
+            shared.config.task_handlers.contains_key(&task),
+            "no handler is registered for {:?}",
+            task
+        );
+
+        if let Some(spill) = &shared.spill {
+            // Once anything is on disk, later tasks follow it there so they
+            // keep their order.
+            if spill.len() > 0 || shared.queued.load(Ordering::SeqCst) >= spill.threshold() {
+                // Shutdown loads the spill back under the same lock before
+                // closing the queue, so nothing may be spilled once it has
+                // begun, and a task spilled before then is always loaded.
+                let reload = spill.lock_reload();
+                if shared.shutting_down.load(Ordering::SeqCst) {
+                    drop(reload);
+                    panic!("{}", ExecuteError::ShutDown);
+                }
+
+                shared.pending.fetch_add(1, Ordering::SeqCst);
+                let pushed = spill.push(task, &payload);
+                drop(reload);
+
+                match pushed {
+                    Ok(()) => {
+                        // The workers may have drained the queue while the
+                        // task was being written.
+                        shared.refill();
+                        return;
+                    }
+                    Err(err) => {
+                        warn!(
+                            "Could not spill a task to disk, queueing it instead: {}",
+                            err
+                        );
+
+                        let result =
+                            shared.submit(0, QueuedJob::new(shared.task_job(task, payload)));
+                        shared.job_finished();
+                        expect_submitted(result);
+                        return;
+                    }
+                }
+            }
+        }
+
+        expect_submitted(shared.submit(0, QueuedJob::new(shared.task_job(task, payload))));
+    }
+
+    /// See `ThreadPool::execute_with_ctx`.
+    pub fn execute_with_ctx<C, F>(&self, f: F)
+    where
+        C: Send + Sync + 'static,
+        F: FnOnce(&C) + Send + 'static,
+    {
+        let Some(context) = self.context::<C>() else {
+            panic!(
+                "thread pool has no context of type {}",
+                std::any::type_name::<C>()
+            );
+        };
+
+        self.execute(move || f(&context));
+    }
+
+    /// See `ThreadPool::context`.
+    pub fn context<C>(&self) -> Option<Arc<C>>
+    where
+        C: Send + Sync + 'static,
+    {
+        let context = self.shared.config.context.as_ref()?;
+
+        Arc::clone(context).downcast().ok()
+    }
+
+    /// See `ThreadPool::execute_cancellable`.
+    pub fn execute_cancellable<F>(&self, token: CancellationToken, f: F) -> mpsc::Receiver<bool>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let (outcome, receiver) = cancel::Outcome::new();
+        let job = QueuedJob {
+            token: Some(token),
+            ..QueuedJob::new(Box::new(move || outcome.run(f)))
+        };
+
+        expect_submitted(self.shared.submit(0, job));
+
+        receiver
+    }
+
+    /// See `ThreadPool::execute_with_deadline`.
+    pub fn execute_with_deadline<F>(
+        &self,
+        deadline: Instant,
+        f: F,
+    ) -> mpsc::Receiver<DeadlineOutcome>
+    where
+        F: FnOnce(&DeadlineToken) + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+
+        self.execute(move || {
+            let outcome = if Instant::now() >= deadline {
+                DeadlineOutcome::Skipped
+            } else {
+                let token = DeadlineToken::new(deadline);
+                f(&token);
+
+                if token.was_observed() {
+                    DeadlineOutcome::Expired
+                } else {
+                    DeadlineOutcome::Completed
+                }
+            };
+
+            let _ = sender.send(outcome);
+        });
+
+        receiver
+    }
+
+    /// See `ThreadPool::execute_async`.
+    #[cfg(feature = "tokio")]
+    pub async fn execute_async<F>(&self, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let permit = match &self.shared.permits {
+            Some(permits) => Some(
+                Arc::clone(permits)
+                    .acquire_owned()
+                    .await
+                    .expect("queue permits are never closed"),
+            ),
+            None => None,
+        };
+
+        self.execute(move || {
+            drop(permit);
+            f();
+        });
+    }
+
+    /// See `ThreadPool::execute_limited`.
+    pub fn execute_limited<F>(&self, key: SemaphoreKey, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        match self.shared.limits.get(&key) {
+            Some(semaphore) => {
+                let semaphore = Arc::clone(semaphore);
+
+                self.execute(move || {
+                    let _permit = semaphore.acquire();
+                    f();
+                });
+            }
+            None => {
+                warn!("No concurrency limit is set for '{}'.", key);
+                self.execute(f);
+            }
+        }
+    }
+
+    /// See `ThreadPool::execute_weighted`.
+    pub fn execute_weighted<F>(&self, weight: u32, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let Some(weights) = &self.shared.weights else {
+            warn!("No maximum concurrent weight is set.");
+            self.execute(f);
+            return;
+        };
+
+        let max = self.shared.config.max_concurrent_weight.unwrap_or_default();
+        assert!(
+            weight <= max,
+            "job weight {} is above the maximum concurrent weight of {}",
+            weight,
+            max
+        );
+
+        let weights = Arc::clone(weights);
+
+        self.execute(move || {
+            let _permit = weights.acquire_many(weight as usize);
+            f();
+        });
+    }
+
+    /// See `ThreadPool::install_signal_handler`.
+    #[cfg(feature = "signal")]
+    pub fn install_signal_handler(&self) -> std::io::Result<()> {
+        signal::install(&self.shared)
+    }
+
+    /// See `ThreadPool::execute_all`.
+    pub fn execute_all<I, F>(&self, jobs: I)
+    where
+        I: IntoIterator<Item = F>,
+        F: FnOnce() + Send + 'static,
+    {
+        let jobs = jobs
+            .into_iter()
+            .map(|f| QueuedJob::new(Box::new(f)))
+            .collect();
+
+        expect_submitted(self.shared.submit_all(jobs));
+    }
+
+    /// See `ThreadPool::map`.
+    pub fn map<T, R, F>(&self, items: Vec<T>, f: F) -> Vec<R>
+    where
+        T: Send + 'static,
+        R: Send + 'static,
+        F: Fn(T) -> R + Send + Sync + 'static,
+    {
+        let count = items.len();
+        let f = Arc::new(f);
+        let (sender, receiver) = mpsc::channel();
+
+        self.execute_all(items.into_iter().enumerate().map(|(index, item)| {
+            let f = Arc::clone(&f);
+            let sender = sender.clone();
+
+            move || {
+                let result = panic::catch_unwind(AssertUnwindSafe(|| f(item)));
+                let _ = sender.send((index, result));
+            }
+        }));
+        drop(sender);
+
+        let mut results: Vec<Option<R>> = (0..count).map(|_| None).collect();
+
+        for (index, result) in receiver {
+            match result {
+                Ok(value) => results[index] = Some(value),
+                Err(payload) => panic::resume_unwind(payload),
+            }
+        }
+
+        results
+            .into_iter()
+            .map(|result| result.expect("map job was dropped without running"))
+            .collect()
+    }
+
+    /// See `ThreadPool::pipeline`.
+    pub fn pipeline<T, R, F>(&self, input: mpsc::Receiver<T>, f: F) -> mpsc::Receiver<R>
+    where
+        T: Send + 'static,
+        R: Send + 'static,
+        F: Fn(T) -> R + Send + Sync + 'static,
+    {
+        let shared = Arc::clone(&self.shared);
+        let f = Arc::new(f);
+        let (sender, receiver) = mpsc::channel();
+
+        thread::Builder::new()
+            .name("yarws-pipeline".to_string())
+            .spawn(move || {
+                for item in input {
+                    let f = Arc::clone(&f);
+                    let sender = sender.clone();
+                    let job = Box::new(move || {
+                        let _ = sender.send(f(item));
+                    });
+
+                    if shared.submit(0, QueuedJob::new(job)).is_err() {
+                        break;
+                    }
+                }
+
+                debug!("Pipeline feeder exiting.");
+            })
+            .expect("failed to spawn pipeline thread");
+
+        receiver
+    }
+
+    /// See `ThreadPool::try_execute`.
+    pub fn try_execute<F>(&self, f: F) -> Result<(), ExecuteError>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.shared
+            .try_submit(0, QueuedJob::new(Box::new(f)))
+            .map_err(ExecuteError::from)
+    }
+
+    /// See `ThreadPool::collector`.
+    pub fn collector<T>(&self) -> Collector<T>
+    where
+        T: Send + 'static,
+    {
+        Collector::new(self.clone())
+    }
+
+    /// See `ThreadPool::group`.
+    pub fn group(&self) -> JobGroup {
+        JobGroup::new(self.clone())
+    }
+
+    /// See `ThreadPool::sender`.
+    pub fn sender(&self) -> JobSender {
+        JobSender::new(self.clone())
+    }
+
+    /// See `ThreadPool::execute_on`.
+    pub fn execute_on<F>(&self, worker_id: usize, f: F) -> Result<(), ExecuteError>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.shared
+            .submit_to(worker_id, QueuedJob::new(Box::new(f)))
+            .map_err(ExecuteError::from)
+    }
+
+    /// See `ThreadPool::broadcast`.
+    pub fn broadcast<F>(&self, f: F)
+    where
+        F: Fn(usize) + Send + Sync + 'static,
+    {
+        if self.shared.config.immediate {
+            f(0);
+            return;
+        }
+        assert!(
+            !self.shared.on_worker(),
+            "broadcast called from a job on the same pool"
+        );
+
+        let broadcast = Broadcast::new(f);
+        let workers = self
+            .shared
+            .queue
+            .push_each(|| Message::Broadcast(broadcast.deliver()));
+        debug!("Broadcast to {} worker(s).", workers);
+
+        broadcast.wait();
+    }
+
+    /// See `ThreadPool::execute_with_result`.
+    pub fn execute_with_result<F, T>(&self, f: F) -> mpsc::Receiver<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+
+        self.execute(move || {
+            // The channel is freed with whichever end goes last, so an unread
+            // result does not outlive a dropped receiver.
+            let _ = sender.send(f());
+        });
+
+        receiver
+    }
+
+    /// See `ThreadPool::execute_fallible`.
+    pub fn execute_fallible<F, E>(&self, f: F)
+    where
+        F: FnOnce() -> Result<(), E> + Send + 'static,
+        E: fmt::Debug + Send + 'static,
+    {
+        let shared = Arc::clone(&self.shared);
+
+        self.execute(move || {
+            let Err(err) = f() else {
+                return;
+            };
+
+            let errors = lock(&shared.errors);
+            let sender = errors
+                .as_ref()
+                .and_then(|sender| sender.downcast_ref::<mpsc::Sender<E>>());
+
+            match sender {
+                // A dropped receiver means nobody wants the error any more.
+                Some(sender) => {
+                    let _ = sender.send(err);
+                }
+                None => error!("Job failed: {:?}", err),
+            }
+        });
+    }
+
+    /// See `ThreadPool::errors`.
+    pub fn errors<E>(&self) -> mpsc::Receiver<E>
+    where
+        E: Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel::<E>();
+
+        *lock(&self.shared.errors) = Some(Box::new(sender));
+
+        receiver
+    }
+
+    /// See `ThreadPool::execute_with_retry`.
+    pub fn execute_with_retry<F, E>(&self, attempts: u32, f: F) -> mpsc::Receiver<Result<(), E>>
+    where
+        F: Fn() -> Result<(), E> + Send + Sync + 'static,
+        E: fmt::Debug + Send + 'static,
+    {
+        assert!(attempts > 0, "a job needs at least one attempt");
+
+        self.execute_with_result(move || {
+            let mut attempt = 1;
+
+            loop {
+                match f() {
+                    Ok(()) => return Ok(()),
+                    Err(err) if attempt < attempts => {
+                        warn!(
+                            "Job attempt {} of {} failed, retrying: {:?}",
+                            attempt, attempts, err
+                        );
+                        attempt += 1;
+                    }
+                    Err(err) => {
+                        error!("Job failed after {} attempt(s): {:?}", attempts, err);
+                        return Err(err);
+                    }
+                }
+            }
+        })
+    }
+
+    /// See `ThreadPool::execute_with_handle`.
+    pub fn execute_with_handle<F, T>(&self, f: F) -> JobHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (job, handle) = JobHandle::wrap(f);
+        let job = QueuedJob {
+            token: Some(handle.token().clone()),
+            ..QueuedJob::new(Box::new(job))
+        };
+
+        expect_submitted(self.shared.submit(0, job));
+
+        handle
+    }
+
+    /// See `ThreadPool::spawn_blocking`.
+    pub fn spawn_blocking<F, R>(&self, f: F) -> BlockingHandle<R>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        self.execute_with_handle(f)
+    }
+
+    /// See `ThreadPool::execute_mut`.
+    pub fn execute_mut<F>(&self, mut f: F) -> JobHandle<F>
+    where
+        F: FnMut() + Send + 'static,
+    {
+        self.execute_with_handle(move || {
+            f();
+            f
+        })
+    }
+
+    /// See `ThreadPool::fork_join`.
+    pub fn fork_join<A, B, RA, RB>(&self, a: A, b: B) -> (RA, RB)
+    where
+        A: FnOnce() -> RA + Send,
+        B: FnOnce() -> RB,
+        RA: Send,
+    {
+        fork::fork_join(&self.shared, a, b)
+    }
+
+    /// See `ThreadPool::scatter_gather`.
+    pub fn scatter_gather<F, R>(&self, tasks: Vec<F>) -> Vec<R>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        fork::scatter_gather(&self.shared, tasks)
+    }
+
+    /// See `ThreadPool::execute_future`.
+    pub fn execute_future<F, T>(&self, f: F) -> PoolFuture<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (future, completer) = future::pair();
+
+        self.execute(move || completer.complete(f));
+
+        future
+    }
+
+    /// See `ThreadPool::spawn`.
+    pub fn spawn<Fut>(&self, fut: Fut) -> PoolFuture<Fut::Output>
+    where
+        Fut: Future + Send + 'static,
+        Fut::Output: Send + 'static,
+    {
+        let (future, completer) = future::pair();
+
+        self.execute(move || completer.complete(|| future::block_on(fut)));
+
+        future
+    }
+}
+
+impl fmt::Debug for ThreadPoolHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ThreadPoolHandle")
+            .field("active", &self.active_count())
+            .field("queued", &self.queued_count())
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Debug for ThreadPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ThreadPool")
+            .field("size", &self.size())
+            .field("active", &self.active_count())
+            .field("queued", &self.queued_count())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for ThreadPool {
+    fn default() -> ThreadPool {
+        ThreadPool::with_num_cpus()
+    }
+}
+
+impl Drop for ThreadPool {
+    fn drop(&mut self) {
+        // Already shut down by `join`, `shutdown_timeout` or another
+        // consuming shutdown method.
+        if self.handle.shared.shutting_down.load(Ordering::SeqCst) {
+            return;
+        }
+
+        if !std::thread::panicking() {
+            self.shut_down();
+            return;
+        }
+
+        // Worker panics only ever become warnings while joining, but any
+        // other panic escaping here would abort the process, since the
+        // thread is already unwinding.
+        if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| self.shut_down())) {
+            error!(
+                "Shutting down the pool while unwinding panicked: {}",
+                panic_message(&*payload)
+            );
+        }
+    }
+}
+
+/// Holds a worker's thread handle.
+///
+/// The worker thread replaces the handle itself when it respawns and clears
+/// it when it dies, so the slot is empty exactly when the worker is gone.
+type ThreadSlot = Arc<Mutex<Option<thread::JoinHandle<()>>>>;
+
+/// What a worker's thread records about its jobs for the pool to read.
+///
+/// Kept across respawns, like the thread slot.
+struct WorkerState {
+    /// Name of the job the worker is running, if that job has one.
+    current: Mutex<Option<String>>,
+    /// Total time spent running jobs.
+    busy_nanos: AtomicU64,
+}
+
+impl WorkerState {
+    fn new() -> WorkerState {
+        WorkerState {
+            current: Mutex::new(None),
+            busy_nanos: AtomicU64::new(0),
+        }
+    }
+}
+
+struct Worker {
+    id: usize,
+    thread: ThreadSlot,
+    state: Arc<WorkerState>,
+    spawned: Instant,
+}
+
+impl fmt::Debug for Worker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Worker")
+            .field("id", &self.id)
+            .field("alive", &self.is_alive())
+            .finish()
+    }
+}
+
+impl Worker {
+    fn new(id: usize, shared: Arc<Shared>) -> Worker {
+        let local = shared.queue.register(id);
+        let thread = Arc::new(Mutex::new(None));
+        let state = Arc::new(WorkerState::new());
+
+        spawn_thread(id, shared, local, Arc::clone(&thread), Arc::clone(&state));
+
+        Worker {
+            id,
+            thread,
+            state,
+            spawned: Instant::now(),
+        }
+    }
+
+    fn is_alive(&self) -> bool {
+        lock(&self.thread).is_some()
+    }
+
+    fn utilization(&self) -> f64 {
+        let busy = self.state.busy_nanos.load(Ordering::SeqCst) as f64;
+        let elapsed = self.spawned.elapsed().as_nanos() as f64;
+
+        if elapsed == 0.0 {
+            return 0.0;
+        }
+
+        (busy / elapsed).min(1.0)
+    }
+
+    fn info(&self) -> Option<WorkerInfo> {
+        let thread = lock(&self.thread);
+        let thread = thread.as_ref()?;
+
+        Some(WorkerInfo {
+            id: self.id,
+            thread_id: thread.thread().id(),
+            alive: !thread.is_finished(),
+        })
+    }
+
+    /// Joins the worker's thread, following it across respawns.
+    ///
+    /// Returns `None` if the worker had already exited, or otherwise whether
+    /// it shut down without panicking.
+    fn join(&self) -> Option<bool> {
+        let mut clean = None;
+
+        // The slot must not stay locked while joining, since an exiting
+        // thread may need it to respawn or mark itself gone.
+        loop {
+            let Some(thread) = lock(&self.thread).take() else {
+                break;
+            };
+            clean = Some(thread.join().is_ok());
+
+            if clean == Some(false) {
+                warn!("Worker {} panicked before shutting down.", self.id);
+            }
+        }
+
+        clean
+    }
+}
+
+fn spawn_thread(
+    id: usize,
+    shared: Arc<Shared>,
+    local: Arc<Local>,
+    slot: ThreadSlot,
+    state: Arc<WorkerState>,
+) {
+    let mut builder = thread::Builder::new();
+
+    if let Some(prefix) = &shared.config.thread_name_prefix {
+        builder = builder.name(format!("{}{}", prefix, id));
+    }
+    if let Some(stack_size) = shared.config.stack_size {
+        builder = builder.stack_size(stack_size);
+    }
+
+    // Hold the slot while spawning so the new thread cannot touch it before
+    // its own handle is stored.
+    let mut guard = lock(&slot);
+    let thread_slot = Arc::clone(&slot);
+
+    let thread = builder.spawn(move || {
+        CURRENT_POOL.with(|pool| pool.set(Arc::as_ptr(&shared)));
+
+        if shared.config.pin_to_cores {
+            pin_to_core(id);
+        }
+        if let Some(priority) = shared.config.thread_priority {
+            set_priority(id, priority);
+        }
+        if shared.config.suppress_panic_output {
+            QUIET_PANICS.with(|quiet| quiet.set(true));
+        }
+        if shared.config.warm_allocator {
+            hint::black_box(vec![0u8; WARM_ALLOCATION_SIZE]);
+        }
+
+        if !init_worker(id, &shared) {
+            shared.stopped_unexpectedly.store(true, Ordering::SeqCst);
+            lock(&thread_slot).take();
+            shared.queue.unregister(&local);
+            return;
+        }
+
+        let exit = worker_loop(id, &shared, &local, &thread_slot, &state);
+        cleanup_worker(id, &shared);
+
+        let payload = match exit {
+            Exit::Stopped => {
+                shared.queue.unregister(&local);
+                return;
+            }
+            Exit::Recycle => {
+                debug!("Replacing worker {} with a fresh thread.", id);
+                spawn_thread(id, Arc::clone(&shared), local, thread_slot, state);
+                return;
+            }
+            Exit::Panicked(payload) => payload,
+        };
+
+        match shared.config.panic_policy {
+            PanicPolicy::Respawn => {
+                debug!("Respawning worker {}.", id);
+                spawn_thread(id, Arc::clone(&shared), local, thread_slot, state);
+                shared.job_finished();
+            }
+            _ => {
+                warn!("Worker {} is exiting after a job panicked.", id);
+                lock(&shared.dead).push(id);
+                shared.stopped_unexpectedly.store(true, Ordering::SeqCst);
+                lock(&thread_slot).take();
+                shared.queue.unregister(&local);
+                shared.job_finished();
+                panic::resume_unwind(payload);
+            }
+        }
+    });
+    *guard = Some(thread.expect("failed to spawn worker thread"));
+}
+
+/// Wraps the panic hook, once per process, so it stays silent on threads
+/// that set `QUIET_PANICS` and behaves as before everywhere else.
+fn install_quiet_panic_hook() {
+    static INSTALLED: Once = Once::new();
+
+    INSTALLED.call_once(|| {
+        let previous = panic::take_hook();
+
+        panic::set_hook(Box::new(move |info| {
+            if !QUIET_PANICS.with(Cell::get) {
+                previous(info);
+            }
+        }));
+    });
+}
+
+/// Runs the pool's `worker_init` hook, if any, and returns whether it
+/// completed without panicking.
+fn init_worker(id: usize, shared: &Shared) -> bool {
+    let Some(Callback(init)) = &shared.config.worker_init else {
+        return true;
+    };
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| init(id)));
+
+    if let Err(payload) = &result {
+        error!("Worker {} init panicked: {}", id, panic_message(&**payload));
+    }
+    if let Some(report) = &*lock(&shared.init_report) {
+        let _ = report.send(result.is_ok());
+    }
+
+    result.is_ok()
+}
+
+/// Runs the pool's `worker_cleanup` hook, if any, logging a panic.
+fn cleanup_worker(id: usize, shared: &Shared) {
+    let Some(Callback(cleanup)) = &shared.config.worker_cleanup else {
+        return;
+    };
+
+    if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| cleanup(id))) {
+        error!(
+            "Worker {} cleanup panicked: {}",
+            id,
+            panic_message(&*payload)
+        );
+    }
+}
+
+/// Why a worker stopped taking jobs.
+enum Exit {
+    /// The worker was told to stop, or retired after being idle.
+    Stopped,
+    /// The worker ran `max_jobs_per_worker` jobs and needs a fresh thread.
+    Recycle,
+    /// A job panicked and the panic policy says the worker must not carry
+    /// on.
+    Panicked(Box<dyn Any + Send>),
+}
+
+/// Runs jobs until the worker is told to stop or has to be replaced.
+fn worker_loop(
+    id: usize,
+    shared: &Arc<Shared>,
+    local: &Local,
+    slot: &ThreadSlot,
+    state: &WorkerState,
+) -> Exit {
+    let mut jobs_run = 0;
+
+    loop {
+        shared.refill();
+
+        let Some(message) = shared.queue.pop(local, shared.config.idle_timeout) else {
+            if shared.queue.is_closed() {
+                if shared.queue.may_stop(local) {
+                    break;
+                }
+                continue;
+            }
+            if shared.retire_idle(slot) {
+                debug!("Worker {} exiting after being idle.", id);
+                return Exit::Stopped;
+            }
+            continue;
+        };
+
+        match message {
+            Message::NewJob(job) => {
+                // The caller finishes the job once it has replaced or removed
+                // this worker, so `wait_for_idle` sees the worker count that
+                // results.
+                if let Err(payload) = run_job(id, shared, job, Some(state)) {
+                    if shared.config.panic_policy != PanicPolicy::Continue {
+                        return Exit::Panicked(payload);
+                    }
+                }
+                shared.job_finished();
+
+                jobs_run += 1;
+                if shared.config.max_jobs_per_worker == Some(jobs_run) {
+                    return Exit::Recycle;
+                }
+            }
+            Message::Broadcast(delivery) => {
+                trace!("Worker {} got a broadcast.", id);
+
+                delivery.run(id);
+            }
+            Message::Retire(sender) => {
+                debug!("Worker {} was told to retire.", id);
+
+                let _ = sender.send(id);
+                return Exit::Stopped;
+            }
+        }
+    }
+
+    debug!("Worker {} was told to terminate.", id);
+
+    Exit::Stopped
+}
+
+/// Runs a job taken off the queue on behalf of worker `id`, returning the
+/// panic payload if it panicked.
+///
+/// The job is recorded in the worker's `state`, if given. The job counts as
+/// picked up once this returns, but not as finished.
+fn run_job(
+    id: usize,
+    shared: &Shared,
+    job: QueuedJob,
+    state: Option<&WorkerState>,
+) -> Result<(), Box<dyn Any + Send>> {
+    let QueuedJob {
+        job,
+        name,
+        token,
+        ticket,
+    } = job;
+    let label = JobLabel(&name);
+
+    // Held until the job counts as started, so jobs start in ticket order.
+    let turn = ticket.map(|ticket| shared.queue.wait_turn(ticket));
+
+    if token.is_some_and(|token| token.is_cancelled()) {
+        trace!("Worker {} skipped a cancelled job{}.", id, label);
+
+        shared.queued.fetch_sub(1, Ordering::SeqCst);
+        shared.worker_freed();
+        return Ok(());
+    }
+
+    if let Some(rate_limiter) = &shared.rate_limiter {
+        rate_limiter.acquire();
+    }
+
+    trace!("Worker {} got a job{}; executing.", id, label);
+
+    shared.queued.fetch_sub(1, Ordering::SeqCst);
+    shared.emit(PoolEvent::JobStarted { worker: id });
+    let active = shared.active.fetch_add(1, Ordering::SeqCst) + 1;
+    shared.peak_active.fetch_max(active, Ordering::SeqCst);
+    if let Some(running) = &shared.running {
+        running.start(id, &name);
+    }
+    // Unnamed jobs leave the name empty, so they never take its lock.
+    let current = state.map(|state| &state.current).filter(|_| name.is_some());
+    if let Some(current) = current {
+        *lock(current) = name.clone();
+    }
+    drop(turn);
+
+    #[cfg(feature = "tracing")]
+    let span = tracing::info_span!(
+        "job",
+        worker = id,
+        name = name.as_deref(),
+        duration_us = tracing::field::Empty
+    );
+    let started = Instant::now();
+
+    let result = {
+        #[cfg(feature = "tracing")]
+        let _entered = span.enter();
+
+        panic::catch_unwind(AssertUnwindSafe(job))
+    };
+
+    let duration = started.elapsed();
+    #[cfg(feature = "tracing")]
+    span.record("duration_us", duration.as_micros() as u64);
+    if let Some(running) = &shared.running {
+        running.finish(id);
+    }
+    if let Some(current) = current {
+        lock(current).take();
+    }
+    if let Some(state) = state {
+        state
+            .busy_nanos
+            .fetch_add(duration.as_nanos() as u64, Ordering::SeqCst);
+    }
+    shared.active.fetch_sub(1, Ordering::SeqCst);
+    shared.worker_freed();
+
+    shared.job_completed();
+    if result.is_err() {
+        shared.panicked.fetch_add(1, Ordering::SeqCst);
+        shared.emit(PoolEvent::WorkerPanicked { worker: id });
+    }
+    shared.emit(PoolEvent::JobCompleted {
+        worker: id,
+        duration,
+    });
+
+    result.map_err(|payload| {
+        error!(
+            "Worker {} job{} panicked: {}",
+            id,
+            label,
+            panic_message(&*payload)
+        );
+        payload
+    })
+}
+
+impl From<PushError> for ExecuteError {
+    fn from(err: PushError) -> ExecuteError {
+        match err {
+            PushError::Full => ExecuteError::Full,
+            PushError::Closed => ExecuteError::ShutDown,
+            PushError::Dead => ExecuteError::PoolDead,
+            PushError::NoSuchWorker => ExecuteError::NoSuchWorker,
+        }
+    }
+}
+
+/// Panics with the reason a job could not be submitted, for the `execute`
+/// methods that have no way to report it.
+fn expect_submitted(result: Result<(), PushError>) {
+    if let Err(err) = result {
+        panic!("{}", ExecuteError::from(err));
+    }
+}
+
+/// Locks `mutex`, recovering the guard if it was poisoned.
+///
+/// Jobs never run while the pool's own locks are held, and every job runs
+/// under `catch_unwind`, so a poisoned lock still guards consistent data and
+/// one panic must not cascade into every other worker.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(target_os = "linux")]
+fn set_priority(id: usize, priority: ThreadPriority) {
+    let nice = match priority {
+        ThreadPriority::Low => 10,
+        ThreadPriority::Normal => 0,
+        ThreadPriority::High => -10,
+    };
+
+    // On Linux, `PRIO_PROCESS` with an id of 0 targets just the calling
+    // thread.
+    // SAFETY: `setpriority` only reads its integer arguments.
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) } != 0 {
+        warn!(
+            "Worker {} could not be given {:?} priority: {}",
+            id,
+            priority,
+            std::io::Error::last_os_error()
+        );
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_priority(id: usize, priority: ThreadPriority) {
+    warn!(
+        "Worker {} could not be given {:?} priority: not supported on this platform.",
+        id, priority
+    );
+}
+
+fn pin_to_core(id: usize) {
+    let pinned = core_affinity::get_core_ids()
+        .filter(|cores| !cores.is_empty())
+        .is_some_and(|cores| core_affinity::set_for_current(cores[id % cores.len()]));
+
+    if !pinned {
+        warn!("Worker {} could not be pinned to a CPU core.", id);
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "Box<dyn Any>"
+    }
+}
@@ -0,0 +1,103 @@
+use std::{fmt, sync::PoisonError};
+
+use crate::{
+    lock,
+    sync::{Condvar, Mutex},
+};
+
+/// Names a category of jobs whose concurrency is capped with
+/// `ThreadPoolBuilder::concurrency_limit`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SemaphoreKey(String);
+
+impl SemaphoreKey {
+    pub fn new(name: impl Into<String>) -> SemaphoreKey {
+        SemaphoreKey(name.into())
+    }
+}
+
+impl fmt::Display for SemaphoreKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<&str> for SemaphoreKey {
+    fn from(name: &str) -> SemaphoreKey {
+        SemaphoreKey::new(name)
+    }
+}
+
+impl From<String> for SemaphoreKey {
+    fn from(name: String) -> SemaphoreKey {
+        SemaphoreKey::new(name)
+    }
+}
+
+/// Counting semaphore that caps how many jobs of one category, or how much
+/// weight, run at once.
+///
+/// Permits are granted in the order they were asked for, so a job that needs
+/// many cannot be starved by a stream of jobs that each need few.
+pub(crate) struct Semaphore {
+    state: Mutex<State>,
+    released: Condvar,
+}
+
+struct State {
+    available: usize,
+    /// Ticket for the next caller of `acquire_many`.
+    next_ticket: u64,
+    /// Ticket of the caller whose turn it is.
+    serving: u64,
+}
+
+/// Returns its permits to the semaphore when dropped.
+pub(crate) struct Permit<'a>(&'a Semaphore, usize);
+
+impl Semaphore {
+    pub(crate) fn new(permits: usize) -> Semaphore {
+        Semaphore {
+            state: Mutex::new(State {
+                available: permits,
+                next_ticket: 0,
+                serving: 0,
+            }),
+            released: Condvar::new(),
+        }
+    }
+
+    /// Takes a permit, blocking until one is free.
+    pub(crate) fn acquire(&self) -> Permit<'_> {
+        self.acquire_many(1)
+    }
+
+    /// Takes `n` permits at once, blocking until that many are free and
+    /// every earlier caller has been served.
+    pub(crate) fn acquire_many(&self, n: usize) -> Permit<'_> {
+        let mut state = lock(&self.state);
+        let ticket = state.next_ticket;
+        state.next_ticket += 1;
+
+        let mut state = self
+            .released
+            .wait_while(state, |state| state.serving != ticket || state.available < n)
+            .unwrap_or_else(PoisonError::into_inner);
+
+        state.available -= n;
+        state.serving += 1;
+        // The next ticket may already have enough permits.
+        self.released.notify_all();
+
+        Permit(self, n)
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        lock(&self.0.state).available += self.1;
+        // Only the caller whose turn it is can go, and it is not known which
+        // waiter that is.
+        self.0.released.notify_all();
+    }
+}
@@ -0,0 +1,932 @@
+use std::{
+    collections::VecDeque,
+    hint,
+    sync::{Arc, PoisonError},
+    time::{Duration, Instant},
+};
+
+use yarws_sched::{self as sched, Injector, Scheduled};
+
+use crate::{
+    custom::{PendingJob, UserQueue},
+    lock,
+    sync::{
+        atomic::{self, AtomicBool, AtomicU32, AtomicU64, AtomicUsize},
+        thread, Condvar, Mutex, RwLock,
+    },
+    Dispatch, Message,
+};
+
+/// Bounds on how many times a worker spins waiting for a message before it
+/// goes to sleep. Loom cannot tell a spin from a livelock, so it never spins.
+#[cfg(not(loom))]
+const SPINS: (u32, u32) = (16, 1024);
+#[cfg(loom)]
+const SPINS: (u32, u32) = (0, 0);
+
+/// Bit of `JobQueue::len` set once the queue is closed. Keeping it in the
+/// same word as the count lets a push check and claim in one step.
+const CLOSED: usize = 1 << (usize::BITS - 1);
+
+/// Reason a message could not be pushed onto a `JobQueue`.
+pub(crate) enum PushError {
+    Full,
+    Closed,
+    /// Every worker has died. Only the pool reports this, never the queue
+    /// itself.
+    Dead,
+    /// No worker with the requested id is registered.
+    NoSuchWorker,
+}
+
+/// Work-stealing queue of messages shared by the pool and its workers.
+///
+/// Jobs with the default priority of 0 are spread round-robin across the
+/// workers' local deques. A worker takes jobs from its own deque first and
+/// steals from its siblings when that runs dry, so workers rarely contend on
+/// the same lock.
+///
+/// Jobs with a higher priority go into a shared injector, and are taken
+/// ahead of anything in the local deques, highest priority first and in push
+/// order within a priority. Control messages go into a queue of their own,
+/// which is checked before any job and even while the queue is paused.
+///
+/// With `Dispatch::RoundRobin`, workers never steal, and a worker sleeps
+/// whenever its own deque and the injector are empty.
+///
+/// With `Dispatch::FifoStart`, every job goes into the injector. Each job
+/// gets a ticket as it is taken, and `wait_turn` holds a worker back until
+/// the job before it has started.
+///
+/// With a custom queue, every job goes into it instead and `dispatch` is
+/// ignored.
+///
+/// Once the queue is closed, every worker's `pop` returns `None` as soon as
+/// the queue is empty, so each worker sees the shutdown exactly once no
+/// matter how the remaining messages were distributed. With `stop_each`, a
+/// worker also waits to be told to `stop`.
+pub(crate) struct JobQueue {
+    injector: Mutex<Injector<Message>>,
+    /// Number of entries in `injector`, so workers can skip its lock.
+    injected: AtomicUsize,
+    control: Mutex<VecDeque<Message>>,
+    /// Number of messages in `control`, so workers can skip its lock.
+    controls: AtomicUsize,
+    locals: RwLock<Vec<Arc<Local>>>,
+    /// Set only when the pool was built with `custom_queue`.
+    custom: Option<UserQueue>,
+    next_local: AtomicUsize,
+    next_seq: AtomicU64,
+    /// Number of messages queued anywhere, including ones being inserted,
+    /// with `CLOSED` set once the queue is closed.
+    len: AtomicUsize,
+    capacity: Option<usize>,
+    dispatch: Dispatch,
+    /// Whether workers wait to be told to `stop` after the queue closes,
+    /// rather than stopping as soon as it is empty.
+    stop_each: bool,
+    paused: AtomicBool,
+    sleepers: AtomicUsize,
+    sleep: Mutex<()>,
+    available: Condvar,
+    space: Condvar,
+    /// Next ticket to hand out under `Dispatch::FifoStart`.
+    next_ticket: AtomicU64,
+    /// Ticket of the next job allowed to start.
+    serving: Mutex<u64>,
+    turn: Condvar,
+}
+
+/// A job's turn to start under `Dispatch::FifoStart`, which passes to the
+/// next ticket when dropped.
+pub(crate) struct Turn<'a> {
+    queue: &'a JobQueue,
+}
+
+impl Drop for Turn<'_> {
+    fn drop(&mut self) {
+        *lock(&self.queue.serving) += 1;
+        self.queue.turn.notify_all();
+    }
+}
+
+/// A worker's local deque.
+pub(crate) struct Local {
+    id: usize,
+    deque: Mutex<VecDeque<Entry>>,
+    /// Messages for the owner alone, such as broadcasts and pinned jobs.
+    /// Never stolen. Anything but a job is taken even while the queue is
+    /// paused.
+    inbox: Mutex<VecDeque<Message>>,
+    /// Set by `stop`, for queues whose workers are stopped one at a time.
+    stopped: AtomicBool,
+    /// How long the owner spins before sleeping, adjusted by `spin`.
+    spins: AtomicU32,
+}
+
+type Entry = sched::Entry<Message>;
+
+impl Scheduled for Message {
+    fn is_job(&self) -> bool {
+        matches!(self, Message::NewJob(_))
+    }
+}
+
+impl JobQueue {
+    pub(crate) fn new(
+        capacity: Option<usize>,
+        dispatch: Dispatch,
+        custom: Option<UserQueue>,
+        stop_each: bool,
+    ) -> JobQueue {
+        let dispatch = if custom.is_some() {
+            Dispatch::Shared
+        } else {
+            dispatch
+        };
+
+        JobQueue {
+            injector: Mutex::new(Injector::new()),
+            injected: AtomicUsize::new(0),
+            control: Mutex::new(VecDeque::new()),
+            controls: AtomicUsize::new(0),
+            locals: RwLock::new(Vec::new()),
+            custom,
+            next_local: AtomicUsize::new(0),
+            next_seq: AtomicU64::new(0),
+            len: AtomicUsize::new(0),
+            capacity,
+            dispatch,
+            stop_each,
+            paused: AtomicBool::new(false),
+            sleepers: AtomicUsize::new(0),
+            sleep: Mutex::new(()),
+            available: Condvar::new(),
+            space: Condvar::new(),
+            next_ticket: AtomicU64::new(0),
+            serving: Mutex::new(0),
+            turn: Condvar::new(),
+        }
+    }
+
+    /// Creates a local deque for worker `id`.
+    pub(crate) fn register(&self, id: usize) -> Arc<Local> {
+        let local = Arc::new(Local {
+            id,
+            deque: Mutex::new(VecDeque::new()),
+            inbox: Mutex::new(VecDeque::new()),
+            stopped: AtomicBool::new(false),
+            spins: AtomicU32::new(SPINS.0),
+        });
+
+        self.locals
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Arc::clone(&local));
+
+        local
+    }
+
+    /// Removes a worker's local deque, moving anything left in it, and any
+    /// jobs pinned to the worker, to the injector so the remaining workers
+    /// can pick them up.
+    pub(crate) fn unregister(&self, local: &Local) {
+        self.locals
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|other| other.id != local.id);
+
+        let mut leftover: Vec<Entry> = lock(&local.deque).drain(..).collect();
+        let pinned: Vec<Message> = lock(&local.inbox)
+            .drain(..)
+            .filter(Scheduled::is_job)
+            .collect();
+
+        // Pinned jobs were never counted in `len`.
+        self.len.fetch_add(pinned.len(), atomic::Ordering::SeqCst);
+        leftover.extend(pinned.into_iter().map(|message| Entry {
+            priority: 0,
+            seq: self.next_seq.fetch_add(1, atomic::Ordering::SeqCst),
+            message,
+        }));
+
+        if !leftover.is_empty() {
+            let mut injector = lock(&self.injector);
+
+            self.injected
+                .fetch_add(leftover.len(), atomic::Ordering::SeqCst);
+            injector.extend(leftover);
+            drop(injector);
+
+            self.wake_all();
+        }
+    }
+
+    /// Pushes a message made by `message` onto the inbox of every registered
+    /// worker, returning how many it reached.
+    ///
+    /// Inbox messages do not count towards the queue's length or capacity.
+    pub(crate) fn push_each(&self, mut message: impl FnMut() -> Message) -> usize {
+        let locals = self.locals.read().unwrap_or_else(PoisonError::into_inner);
+
+        for local in locals.iter() {
+            lock(&local.inbox).push_back(message());
+        }
+        let count = locals.len();
+        drop(locals);
+
+        self.wake_all();
+
+        count
+    }
+
+    /// Pushes a job onto the inbox of worker `id`, for that worker alone to
+    /// run.
+    ///
+    /// Pinned jobs do not count towards the queue's length or capacity.
+    pub(crate) fn push_to(&self, id: usize, message: Message) -> Result<(), PushError> {
+        // Claimed only while the job is inserted, so the owner cannot see
+        // the queue closed and empty in between and exit without it.
+        self.claim(1, None)?;
+
+        let locals = self.locals.read().unwrap_or_else(PoisonError::into_inner);
+        let found = match locals.iter().find(|local| local.id == id) {
+            Some(local) => {
+                lock(&local.inbox).push_back(message);
+                true
+            }
+            None => false,
+        };
+        drop(locals);
+        self.len.fetch_sub(1, atomic::Ordering::SeqCst);
+
+        if !found {
+            return Err(PushError::NoSuchWorker);
+        }
+
+        // Only the owner may take the job, so wake everyone to be sure the
+        // owner sees it.
+        self.wake_all();
+
+        Ok(())
+    }
+
+    /// Pushes a message, blocking while the queue is at capacity.
+    pub(crate) fn push(&self, priority: u8, message: Message) -> Result<(), PushError> {
+        loop {
+            match self.reserve() {
+                Err(PushError::Full) => {
+                    let guard = lock(&self.sleep);
+                    let _guard = self
+                        .space
+                        .wait_while(guard, |_| !self.is_closed() && self.is_full())
+                        .unwrap_or_else(PoisonError::into_inner);
+                }
+                Err(err) => return Err(err),
+                Ok(()) => break,
+            }
+        }
+
+        self.insert(priority, message);
+
+        Ok(())
+    }
+
+    /// Pushes a message, evicting the oldest queued jobs while the queue is
+    /// at capacity and passing each one to `evicted`.
+    pub(crate) fn push_evicting(
+        &self,
+        priority: u8,
+        message: Message,
+        mut evicted: impl FnMut(Message),
+    ) -> Result<(), PushError> {
+        loop {
+            match self.reserve() {
+                Err(PushError::Full) => match self.evict_oldest() {
+                    Some(job) => evicted(job),
+                    // Every slot is held by a push still in progress or a
+                    // control message, both of which are about to free up.
+                    None => thread::yield_now(),
+                },
+                Err(err) => return Err(err),
+                Ok(()) => break,
+            }
+        }
+
+        self.insert(priority, message);
+
+        Ok(())
+    }
+
+    /// Pushes a message even if the queue is at capacity.
+    pub(crate) fn push_unbounded(&self, priority: u8, message: Message) -> Result<(), PushError> {
+        self.claim(1, None)?;
+        self.insert(priority, message);
+
+        Ok(())
+    }
+
+    /// Pushes a message if the queue has room, without blocking.
+    pub(crate) fn try_push(&self, priority: u8, message: Message) -> Result<(), PushError> {
+        self.reserve()?;
+        self.insert(priority, message);
+
+        Ok(())
+    }
+
+    /// Pushes a batch of priority 0 messages onto an unbounded queue, taking
+    /// each lock at most once for the whole batch.
+    ///
+    /// Either every message is pushed or, if the queue is closed, none are.
+    pub(crate) fn push_all(&self, messages: Vec<Message>) -> Result<(), PushError> {
+        debug_assert!(self.capacity.is_none());
+
+        let count = messages.len();
+        self.claim(count, None)?;
+
+        if self.custom.is_some() {
+            for message in messages {
+                self.insert(0, message);
+            }
+
+            return Ok(());
+        }
+
+        let seq = self
+            .next_seq
+            .fetch_add(count as u64, atomic::Ordering::SeqCst);
+        let entries = messages.into_iter().zip(seq..).map(|(message, seq)| Entry {
+            priority: 0,
+            seq,
+            message,
+        });
+
+        let locals = self.locals.read().unwrap_or_else(PoisonError::into_inner);
+
+        if locals.is_empty() || self.is_fifo_start() {
+            let mut injector = lock(&self.injector);
+
+            self.injected.fetch_add(count, atomic::Ordering::SeqCst);
+            injector.extend(entries);
+        } else {
+            let start = self.next_local.fetch_add(count, atomic::Ordering::Relaxed);
+            let shares = sched::spread(entries, locals.len(), start);
+
+            for (local, share) in locals.iter().zip(shares) {
+                if !share.is_empty() {
+                    lock(&local.deque).extend(share);
+                }
+            }
+        }
+        drop(locals);
+
+        self.wake_all();
+
+        Ok(())
+    }
+
+    /// Pushes a control message, ignoring both capacity and closure.
+    ///
+    /// Control messages are taken ahead of every job, so a worker answers one
+    /// as soon as it finishes its current job. They do not count towards the
+    /// queue's length or capacity.
+    pub(crate) fn push_control(&self, message: Message) {
+        let mut control = lock(&self.control);
+
+        control.push_back(message);
+        self.controls.fetch_add(1, atomic::Ordering::SeqCst);
+        drop(control);
+
+        self.wake_one();
+    }
+
+    /// Removes every job from the queue, in the order they would have been
+    /// taken. Control messages stay queued.
+    pub(crate) fn drain_jobs(&self) -> Vec<Message> {
+        let mut drained = Vec::new();
+        let mut custom = Vec::new();
+
+        if let Some(UserQueue(queue)) = &self.custom {
+            while let Some(job) = queue.pop() {
+                custom.push(Message::NewJob(job.job));
+            }
+        }
+
+        {
+            let jobs = lock(&self.injector).take_jobs();
+
+            self.injected
+                .fetch_sub(jobs.len(), atomic::Ordering::SeqCst);
+            drained.extend(jobs);
+        }
+
+        let mut pinned = Vec::new();
+
+        for local in self
+            .locals
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+        {
+            drained.extend(lock(&local.deque).drain(..));
+
+            let mut inbox = lock(&local.inbox);
+            let (jobs, rest): (VecDeque<_>, VecDeque<_>) =
+                inbox.drain(..).partition(Scheduled::is_job);
+            *inbox = rest;
+            pinned.extend(jobs);
+        }
+
+        self.len
+            .fetch_sub(drained.len() + custom.len(), atomic::Ordering::SeqCst);
+        self.wake_all();
+        {
+            let _guard = lock(&self.sleep);
+            self.space.notify_all();
+        }
+
+        drained.sort_by(|a, b| b.cmp(a));
+        custom
+            .into_iter()
+            .chain(drained.into_iter().map(|entry| entry.message))
+            .chain(pinned)
+            .collect()
+    }
+
+    /// Stops the queue from accepting new jobs and tells the workers to exit
+    /// once everything already queued has been taken.
+    pub(crate) fn close(&self) {
+        self.len.fetch_or(CLOSED, atomic::Ordering::SeqCst);
+
+        let _guard = lock(&self.sleep);
+        self.space.notify_all();
+        self.available.notify_all();
+    }
+
+    /// Accepts messages again after `close`, for a pool being restarted.
+    pub(crate) fn reopen(&self) {
+        self.len.fetch_and(!CLOSED, atomic::Ordering::SeqCst);
+    }
+
+    /// Lets worker `id` stop once the closed queue is empty, for queues built
+    /// with `stop_each`.
+    pub(crate) fn stop(&self, id: usize) {
+        let locals = self.locals.read().unwrap_or_else(PoisonError::into_inner);
+
+        if let Some(local) = locals.iter().find(|local| local.id == id) {
+            local.stopped.store(true, atomic::Ordering::SeqCst);
+        }
+        drop(locals);
+
+        self.wake_all();
+    }
+
+    /// Returns whether the owner of `local` may stop once the closed queue is
+    /// empty.
+    pub(crate) fn may_stop(&self, local: &Local) -> bool {
+        !self.stop_each || local.stopped.load(atomic::Ordering::SeqCst)
+    }
+
+    /// Stops workers from taking messages until `resume` is called.
+    pub(crate) fn pause(&self) {
+        self.paused.store(true, atomic::Ordering::SeqCst);
+    }
+
+    pub(crate) fn resume(&self) {
+        let _guard = lock(&self.sleep);
+        self.paused.store(false, atomic::Ordering::SeqCst);
+        self.available.notify_all();
+    }
+
+    /// Returns whether workers are held back by `pause`. Closing the queue
+    /// lifts the pause, so shutdown can drain it.
+    pub(crate) fn is_paused(&self) -> bool {
+        self.paused.load(atomic::Ordering::SeqCst) && !self.is_closed()
+    }
+
+    pub(crate) fn is_closed(&self) -> bool {
+        self.len.load(atomic::Ordering::SeqCst) & CLOSED != 0
+    }
+
+    /// Returns the number of messages queued or being inserted.
+    fn count(&self) -> usize {
+        self.len.load(atomic::Ordering::SeqCst) & !CLOSED
+    }
+
+    /// Returns whether no messages are queued or being inserted.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.count() == 0 && self.controls.load(atomic::Ordering::SeqCst) == 0
+    }
+
+    /// Returns the number of workers waiting for a message.
+    pub(crate) fn sleepers(&self) -> usize {
+        self.sleepers.load(atomic::Ordering::SeqCst)
+    }
+
+    fn is_round_robin(&self) -> bool {
+        self.dispatch == Dispatch::RoundRobin
+    }
+
+    fn is_fifo_start(&self) -> bool {
+        self.dispatch == Dispatch::FifoStart
+    }
+
+    fn is_full(&self) -> bool {
+        self.capacity
+            .is_some_and(|capacity| self.count() >= capacity)
+    }
+
+    /// Claims a slot for one more message.
+    fn reserve(&self) -> Result<(), PushError> {
+        self.claim(1, self.capacity)
+    }
+
+    /// Claims `n` slots, unless the queue is closed or that would take it
+    /// past `capacity`.
+    ///
+    /// The check and the claim are one update of `len`, which also holds
+    /// `CLOSED`, so a push either lands before the queue closes, and the
+    /// workers wait for it, or fails.
+    fn claim(&self, n: usize, capacity: Option<usize>) -> Result<(), PushError> {
+        self.len
+            .fetch_update(atomic::Ordering::SeqCst, atomic::Ordering::SeqCst, |len| {
+                let fits = capacity.is_none_or(|capacity| len + n <= capacity);
+
+                (len & CLOSED == 0 && fits).then_some(len + n)
+            })
+            .map(drop)
+            .map_err(|len| {
+                if len & CLOSED != 0 {
+                    PushError::Closed
+                } else {
+                    PushError::Full
+                }
+            })
+    }
+
+    /// Inserts a message whose slot has already been reserved.
+    fn insert(&self, priority: u8, message: Message) {
+        let message = match (&self.custom, message) {
+            (Some(UserQueue(queue)), Message::NewJob(job)) => {
+                queue.push(PendingJob { job, priority });
+                self.wake_one();
+                return;
+            }
+            (_, message) => message,
+        };
+
+        let entry = Entry {
+            priority,
+            seq: self.next_seq.fetch_add(1, atomic::Ordering::SeqCst),
+            message,
+        };
+
+        let entry = if priority == 0 && entry.is_job() && !self.is_fifo_start() {
+            self.push_local(entry)
+        } else {
+            Some(entry)
+        };
+
+        match entry {
+            Some(entry) => {
+                let mut injector = lock(&self.injector);
+
+                self.injected.fetch_add(1, atomic::Ordering::SeqCst);
+                injector.push(entry);
+                drop(injector);
+
+                self.wake_one();
+            }
+            // Only the deque's owner may take the job, so wake everyone to
+            // be sure the owner sees it.
+            None if self.is_round_robin() => self.wake_all(),
+            None => self.wake_one(),
+        }
+    }
+
+    /// Pushes an entry onto the next local deque, handing it back if no
+    /// worker is registered.
+    fn push_local(&self, entry: Entry) -> Option<Entry> {
+        let locals = self.locals.read().unwrap_or_else(PoisonError::into_inner);
+
+        if locals.is_empty() {
+            return Some(entry);
+        }
+
+        let index = self.next_local.fetch_add(1, atomic::Ordering::Relaxed) % locals.len();
+        lock(&locals[index].deque).push_back(entry);
+
+        None
+    }
+
+    fn wake_one(&self) {
+        if self.sleepers.load(atomic::Ordering::SeqCst) > 0 {
+            let _guard = lock(&self.sleep);
+            self.available.notify_one();
+        }
+    }
+
+    fn wake_all(&self) {
+        if self.sleepers.load(atomic::Ordering::SeqCst) > 0 {
+            let _guard = lock(&self.sleep);
+            self.available.notify_all();
+        }
+    }
+
+    /// Pops the next message for the worker owning `local`, blocking until
+    /// one is available.
+    ///
+    /// Returns `None` once the queue is closed and empty, or if `timeout`
+    /// passes without finding anything to take.
+    pub(crate) fn pop(&self, local: &Local, timeout: Option<Duration>) -> Option<Message> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        // Spin at most once between sleeps, since a message another worker
+        // owns would otherwise keep this one spinning.
+        let mut spun = false;
+
+        loop {
+            if let Some(message) = self.pop_inbox(local).or_else(|| self.pop_control()) {
+                return Some(message);
+            }
+
+            let entry = if self.is_paused() {
+                None
+            } else {
+                self.find(local)
+            };
+
+            if let Some(entry) = entry {
+                return Some(self.taken(entry));
+            }
+
+            if !spun {
+                spun = true;
+
+                if self.spin(local) {
+                    continue;
+                }
+            }
+
+            let guard = lock(&self.sleep);
+            self.sleepers.fetch_add(1, atomic::Ordering::SeqCst);
+
+            // One load for both, so a push claimed before the queue closed
+            // cannot be missed between them.
+            let len = self.len.load(atomic::Ordering::SeqCst);
+            let empty = len & !CLOSED == 0;
+            let closed = len & CLOSED != 0;
+            let no_control = self.controls.load(atomic::Ordering::SeqCst) == 0;
+            let (inbox_empty, inbox_ready) = {
+                let inbox = lock(&local.inbox);
+                let ready = inbox
+                    .iter()
+                    .any(|message| !message.is_job() || !self.is_paused());
+
+                (inbox.is_empty(), ready)
+            };
+
+            if empty && no_control && inbox_empty && closed && self.may_stop(local) {
+                self.sleepers.fetch_sub(1, atomic::Ordering::SeqCst);
+                return None;
+            }
+
+            let idle = !inbox_ready
+                && no_control
+                && (empty || self.is_paused() || (self.is_round_robin() && self.is_idle(local)));
+
+            if idle {
+                match deadline {
+                    None => {
+                        let _guard = self
+                            .available
+                            .wait(guard)
+                            .unwrap_or_else(PoisonError::into_inner);
+                    }
+                    Some(deadline) => {
+                        let now = Instant::now();
+
+                        if now >= deadline {
+                            self.sleepers.fetch_sub(1, atomic::Ordering::SeqCst);
+                            return None;
+                        }
+
+                        let _guard = self
+                            .available
+                            .wait_timeout(guard, deadline - now)
+                            .unwrap_or_else(PoisonError::into_inner);
+                    }
+                }
+                self.sleepers.fetch_sub(1, atomic::Ordering::SeqCst);
+                spun = false;
+            } else {
+                // A message is being inserted; give the pusher a moment.
+                self.sleepers.fetch_sub(1, atomic::Ordering::SeqCst);
+                drop(guard);
+                thread::yield_now();
+            }
+        }
+    }
+
+    /// Takes the next message from `local`'s inbox. While the queue is paused,
+    /// pinned jobs stay put and only other messages are taken.
+    fn pop_inbox(&self, local: &Local) -> Option<Message> {
+        let mut inbox = lock(&local.inbox);
+
+        if self.is_paused() {
+            let index = inbox.iter().position(|message| !message.is_job())?;
+            inbox.remove(index)
+        } else {
+            inbox.pop_front()
+        }
+    }
+
+    fn pop_control(&self) -> Option<Message> {
+        if self.controls.load(atomic::Ordering::SeqCst) == 0 {
+            return None;
+        }
+
+        let mut control = lock(&self.control);
+        let message = control.pop_front()?;
+        self.controls.fetch_sub(1, atomic::Ordering::SeqCst);
+
+        Some(message)
+    }
+
+    /// Spins briefly waiting for a message to be queued, returning whether
+    /// one was.
+    ///
+    /// Jobs often arrive moments apart, and waking a sleeping worker costs a
+    /// syscall on both sides. The owner of `local` spins for its budget,
+    /// which doubles when spinning pays off and halves when it does not, so
+    /// workers on a quiet pool soon go almost straight to sleep.
+    fn spin(&self, local: &Local) -> bool {
+        if self.is_paused() || self.is_closed() {
+            return false;
+        }
+
+        let (min, max) = SPINS;
+        let budget = local.spins.load(atomic::Ordering::Relaxed);
+
+        for _ in 0..budget {
+            hint::spin_loop();
+
+            if self.count() > 0 {
+                let budget = budget.saturating_mul(2).clamp(min, max);
+                local.spins.store(budget, atomic::Ordering::Relaxed);
+                return true;
+            }
+        }
+
+        local
+            .spins
+            .store((budget / 2).max(min), atomic::Ordering::Relaxed);
+        false
+    }
+
+    /// Pops the next message without blocking, for a queue no worker is
+    /// registered with.
+    #[cfg(feature = "manual")]
+    pub(crate) fn try_pop(&self) -> Option<Message> {
+        let entry = match &self.custom {
+            Some(UserQueue(queue)) => queue.pop().map(|job| Entry {
+                priority: job.priority,
+                seq: 0,
+                message: Message::NewJob(job.job),
+            }),
+            None => None,
+        };
+
+        entry
+            .or_else(|| self.pop_injector(|_| true))
+            .map(|entry| self.taken(entry))
+    }
+
+    /// Accounts for an entry having been taken off the queue.
+    fn taken(&self, entry: Entry) -> Message {
+        self.len.fetch_sub(1, atomic::Ordering::SeqCst);
+
+        if self.capacity.is_some() {
+            let _guard = lock(&self.sleep);
+            self.space.notify_one();
+        }
+        // Workers with nothing of their own wait for the queue to drain
+        // before exiting.
+        if self.is_round_robin() && self.is_closed() {
+            self.wake_all();
+        }
+
+        entry.message
+    }
+
+    /// Removes the job that has been queued the longest, or with a custom
+    /// queue the job it would hand out next.
+    fn evict_oldest(&self) -> Option<Message> {
+        if let Some(UserQueue(queue)) = &self.custom {
+            return queue.pop().map(|job| {
+                self.taken(Entry {
+                    priority: job.priority,
+                    seq: 0,
+                    message: Message::NewJob(job.job),
+                })
+            });
+        }
+
+        let locals = self.locals.read().unwrap_or_else(PoisonError::into_inner);
+        // Local deques only hold jobs, each in push order.
+        let local = locals
+            .iter()
+            .filter_map(|local| Some((lock(&local.deque).front()?.seq, local)))
+            .min_by_key(|&(seq, _)| seq);
+
+        let mut injector = lock(&self.injector);
+        let entry = match (local, injector.oldest_job()) {
+            (Some((seq, local)), injected) if injected.is_none_or(|oldest| seq < oldest) => {
+                drop(injector);
+                lock(&local.deque).pop_front()
+            }
+            (_, Some(_)) => {
+                self.injected.fetch_sub(1, atomic::Ordering::SeqCst);
+                injector.take_oldest_job()
+            }
+            (_, None) => None,
+        };
+
+        entry.map(|entry| self.taken(entry))
+    }
+
+    /// Returns whether there is nothing for `local`'s owner to take without
+    /// stealing.
+    fn is_idle(&self, local: &Local) -> bool {
+        self.injected.load(atomic::Ordering::SeqCst) == 0 && lock(&local.deque).is_empty()
+    }
+
+    fn find(&self, local: &Local) -> Option<Entry> {
+        if let Some(UserQueue(queue)) = &self.custom {
+            return queue
+                .pop()
+                .map(|job| Entry {
+                    priority: job.priority,
+                    seq: 0,
+                    message: Message::NewJob(job.job),
+                })
+                .or_else(|| self.pop_injector(|_| true));
+        }
+
+        self.pop_injector(|entry| entry.is_job() && entry.priority > 0)
+            .or_else(|| lock(&local.deque).pop_front())
+            .or_else(|| match self.dispatch {
+                Dispatch::Shared => self.steal(local),
+                Dispatch::RoundRobin | Dispatch::FifoStart => None,
+            })
+            .or_else(|| self.pop_injector(|_| true))
+    }
+
+    fn pop_injector(&self, accept: impl FnOnce(&Entry) -> bool) -> Option<Entry> {
+        if self.injected.load(atomic::Ordering::SeqCst) == 0 {
+            return None;
+        }
+
+        let mut injector = lock(&self.injector);
+
+        if !accept(injector.peek()?) {
+            return None;
+        }
+
+        self.injected.fetch_sub(1, atomic::Ordering::SeqCst);
+        let mut entry = injector.pop()?;
+
+        // Handed out under the lock, so tickets follow the order jobs leave
+        // the injector.
+        if let Message::NewJob(job) = &mut entry.message {
+            if self.is_fifo_start() {
+                job.ticket = Some(self.next_ticket.fetch_add(1, atomic::Ordering::SeqCst));
+            }
+        }
+
+        Some(entry)
+    }
+
+    /// Blocks until the job holding `ticket` may start.
+    pub(crate) fn wait_turn(&self, ticket: u64) -> Turn<'_> {
+        let guard = lock(&self.serving);
+        let _guard = self
+            .turn
+            .wait_while(guard, |serving| *serving != ticket)
+            .unwrap_or_else(PoisonError::into_inner);
+
+        Turn { queue: self }
+    }
+
+    fn steal(&self, thief: &Local) -> Option<Entry> {
+        let locals = self.locals.read().unwrap_or_else(PoisonError::into_inner);
+        let start = locals.iter().position(|l| l.id == thief.id).unwrap_or(0);
+
+        locals
+            .iter()
+            .cycle()
+            .skip(start + 1)
+            .take(locals.len())
+            .filter(|victim| victim.id != thief.id)
+            .find_map(|victim| lock(&victim.deque).pop_front())
+    }
+}
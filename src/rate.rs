@@ -0,0 +1,55 @@
+use std::time::{Duration, Instant};
+
+use crate::{
+    lock,
+    sync::{thread, Mutex},
+};
+
+/// Token bucket that limits how many jobs the workers start per second.
+///
+/// The bucket holds up to one second's worth of tokens and starts full, so a
+/// burst of up to `rate` jobs can start straight away.
+pub(crate) struct RateLimiter {
+    rate: f64,
+    bucket: Mutex<Bucket>,
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(rate: u32) -> RateLimiter {
+        RateLimiter {
+            rate: f64::from(rate),
+            bucket: Mutex::new(Bucket {
+                tokens: f64::from(rate),
+                refilled: Instant::now(),
+            }),
+        }
+    }
+
+    /// Takes a token, sleeping until one is available.
+    pub(crate) fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut bucket = lock(&self.bucket);
+                let now = Instant::now();
+                let elapsed = now.duration_since(bucket.refilled).as_secs_f64();
+
+                bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.rate);
+                bucket.refilled = now;
+
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                    return;
+                }
+
+                Duration::from_secs_f64((1.0 - bucket.tokens) / self.rate)
+            };
+
+            thread::sleep(wait);
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use std::{
+    sync::{mpsc, Arc, PoisonError},
+    time::Duration,
+};
+
+use log::debug;
+
+use crate::{
+    lock,
+    sync::{atomic::Ordering, thread, Condvar, Mutex},
+    Shared,
+};
+
+/// Consecutive checks the same pressure must be seen for before the pool is
+/// resized, so a short burst or lull does not make it flap.
+const STEADY_CHECKS: u32 = 3;
+
+/// Monitor thread that grows the pool while jobs pile up in the queue and
+/// shrinks it while workers sit idle.
+///
+/// The pool is resized by one worker at a time, between `min_size` (or one
+/// worker, whichever is more) and `max_size`. A paused or closed pool is
+/// left alone.
+pub(crate) struct AutoScaler {
+    stop: Arc<(Mutex<bool>, Condvar)>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl AutoScaler {
+    pub(crate) fn new(shared: Arc<Shared>, interval: Duration) -> AutoScaler {
+        let stop = Arc::new((Mutex::new(false), Condvar::new()));
+
+        let thread = {
+            let stop = Arc::clone(&stop);
+
+            thread::Builder::new()
+                .name("yarws-scaler".to_string())
+                .spawn(move || run(&stop, &shared, interval))
+                .expect("failed to spawn auto-scaler thread")
+        };
+
+        AutoScaler {
+            stop,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for AutoScaler {
+    fn drop(&mut self) {
+        let (stopped, wakeup) = &*self.stop;
+
+        *lock(stopped) = true;
+        wakeup.notify_one();
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pressure {
+    /// More jobs are queued than the high-water mark.
+    High,
+    /// No more jobs are queued than the low-water mark, and some workers
+    /// are idle.
+    Low,
+    Steady,
+}
+
+/// Returns the pressure on a pool of `workers` workers right now.
+fn check(shared: &Shared, workers: usize) -> Pressure {
+    if shared.queue.is_paused() || shared.queue.is_closed() {
+        return Pressure::Steady;
+    }
+
+    let queued = shared.queued.load(Ordering::SeqCst);
+    let active = shared.active.load(Ordering::SeqCst);
+    let (low, high) = shared.config.scale_thresholds.unwrap_or((0, workers));
+
+    if queued > high && workers < shared.config.scale_max {
+        Pressure::High
+    } else if queued <= low && active < workers && workers > shared.config.min_size.max(1) {
+        Pressure::Low
+    } else {
+        Pressure::Steady
+    }
+}
+
+fn run(stop: &(Mutex<bool>, Condvar), shared: &Arc<Shared>, interval: Duration) {
+    let (stopped, wakeup) = stop;
+    let (sender, receiver) = mpsc::channel();
+    let mut last = Pressure::Steady;
+    let mut streak = 0;
+    let mut guard = lock(stopped);
+
+    while !*guard {
+        guard = wakeup
+            .wait_timeout(guard, interval)
+            .unwrap_or_else(PoisonError::into_inner)
+            .0;
+
+        while let Ok(id) = receiver.try_recv() {
+            shared.join_retired(id);
+        }
+
+        let workers = shared.staying_workers();
+        let pressure = check(shared, workers);
+
+        streak = if pressure == last { streak + 1 } else { 1 };
+        last = pressure;
+
+        if pressure == Pressure::Steady || streak < STEADY_CHECKS {
+            continue;
+        }
+        streak = 0;
+
+        let mut list = lock(&shared.workers);
+
+        // Checked under the lock so no worker is spawned after shutdown has
+        // taken the list.
+        if shared.queue.is_closed() {
+            continue;
+        }
+
+        match pressure {
+            Pressure::High => {
+                debug!("Scaling the pool up to {} workers.", workers + 1);
+                shared.spawn_worker(&mut list);
+                drop(list);
+
+                shared.worker_freed();
+            }
+            Pressure::Low => {
+                // A manual resize since the check may have left nothing to
+                // remove.
+                if shared.retire(&list, 1, &sender).is_ok() {
+                    debug!("Scaling the pool down to {} workers.", workers - 1);
+                }
+            }
+            Pressure::Steady => {}
+        }
+    }
+}
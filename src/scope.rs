@@ -0,0 +1,114 @@
+use std::{
+    any::Any,
+    marker::PhantomData,
+    mem,
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, PoisonError},
+};
+
+use crate::{
+    expect_submitted,
+    sync::{Condvar, Mutex, MutexGuard},
+    Job, QueuedJob, ThreadPool,
+};
+
+/// A scope for running jobs that borrow data from the caller's stack.
+///
+/// Created by `ThreadPool::scope`, which does not return until every job
+/// spawned on the scope has finished.
+pub struct Scope<'pool, 'scope> {
+    pool: &'pool ThreadPool,
+    state: Arc<ScopeState>,
+    // Invariant in 'scope, so jobs cannot borrow data that dies sooner.
+    _scope: PhantomData<&'scope mut &'scope ()>,
+}
+
+#[derive(Default)]
+struct ScopeState {
+    inner: Mutex<Inner>,
+    done: Condvar,
+}
+
+#[derive(Default)]
+struct Inner {
+    pending: usize,
+    panic: Option<Box<dyn Any + Send + 'static>>,
+}
+
+/// Marks a scoped job as finished when dropped, whether or not it ran.
+struct Pending {
+    state: Arc<ScopeState>,
+}
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        let mut inner = self.state.lock();
+        inner.pending -= 1;
+
+        if inner.pending == 0 {
+            self.state.done.notify_all();
+        }
+    }
+}
+
+impl ScopeState {
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<'pool, 'scope> Scope<'pool, 'scope> {
+    pub(crate) fn new(pool: &'pool ThreadPool) -> Scope<'pool, 'scope> {
+        Scope {
+            pool,
+            state: Arc::default(),
+            _scope: PhantomData,
+        }
+    }
+
+    /// Runs `f` on the pool. `f` may borrow anything that outlives the scope.
+    ///
+    /// If `f` panics, the panic is re-raised by `ThreadPool::scope` once all
+    /// of the scope's jobs have finished.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the job queue has been closed because the pool is shutting
+    /// down.
+    pub fn execute<F>(&self, f: F)
+    where
+        F: FnOnce() + Send + 'scope,
+    {
+        self.state.lock().pending += 1;
+
+        let pending = Pending {
+            state: Arc::clone(&self.state),
+        };
+        let job: Box<dyn FnOnce() + Send + 'scope> = Box::new(move || {
+            if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(f)) {
+                pending.state.lock().panic.get_or_insert(payload);
+            }
+            drop(pending);
+        });
+
+        // SAFETY: `ThreadPool::scope` waits for `pending` to reach zero before
+        // returning, and every job decrements it when it runs or is dropped,
+        // so the job never outlives the data it borrows for 'scope.
+        let job: Job = unsafe { mem::transmute::<Box<dyn FnOnce() + Send + 'scope>, Job>(job) };
+
+        expect_submitted(self.pool.handle.shared.submit(0, QueuedJob::new(job)));
+    }
+
+    /// Blocks until every job spawned on the scope has finished, returning
+    /// the payload of the first one that panicked.
+    pub(crate) fn wait(&self) -> Option<Box<dyn Any + Send + 'static>> {
+        let inner = self.state.lock();
+        let mut inner = self
+            .state
+            .done
+            .wait_while(inner, |inner| inner.pending > 0)
+            .unwrap_or_else(PoisonError::into_inner);
+
+        inner.panic.take()
+    }
+}
@@ -0,0 +1,46 @@
+use crate::{ExecuteError, ThreadPoolHandle};
+
+/// A cloneable handle that can only submit jobs to a `ThreadPool`.
+///
+/// Created by `ThreadPool::sender`. Unlike a `ThreadPoolHandle`, it cannot
+/// pause or resume the pool, clear its queue or wait on it, so it can be
+/// handed to code that should only ever add work. Neither can resize or shut
+/// down the pool; only the `ThreadPool` itself can. Like a handle, it does
+/// not keep the pool alive.
+#[derive(Debug, Clone)]
+pub struct JobSender {
+    handle: ThreadPoolHandle,
+}
+
+impl JobSender {
+    pub(crate) fn new(handle: ThreadPoolHandle) -> JobSender {
+        JobSender { handle }
+    }
+
+    /// Runs `f` on the pool, as `ThreadPool::execute` does.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the job queue has been closed because the pool is shutting
+    /// down, or if every worker has died. Use `try_execute` to handle those
+    /// cases instead.
+    pub fn execute<F>(&self, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.handle.execute(f);
+    }
+
+    /// Runs `f` on the pool without blocking, as `ThreadPool::try_execute`
+    /// does.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as `ThreadPool::try_execute`.
+    pub fn try_execute<F>(&self, f: F) -> Result<(), ExecuteError>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.handle.try_execute(f)
+    }
+}
@@ -0,0 +1,61 @@
+use std::{
+    io,
+    sync::{Arc, PoisonError},
+};
+
+use log::{info, warn};
+use signal_hook::{
+    consts::{SIGINT, SIGTERM},
+    iterator::Signals,
+    low_level,
+};
+
+use crate::{lock, sync::atomic::Ordering, Shared};
+
+/// Spawns a thread that drains the pool on the first SIGTERM or SIGINT, then
+/// lets the signal terminate the process as it would have.
+///
+/// The thread only holds a weak reference, so it never keeps a dropped pool
+/// alive.
+pub(crate) fn install(shared: &Arc<Shared>) -> io::Result<()> {
+    let mut signals = Signals::new([SIGTERM, SIGINT])?;
+    let shared = Arc::downgrade(shared);
+
+    // A plain `std` thread: it spends its life blocked on the signal pipe,
+    // which loom cannot model.
+    std::thread::Builder::new()
+        .name("yarws-signal".to_string())
+        .spawn(move || {
+            let Some(signal) = signals.forever().next() else {
+                return;
+            };
+
+            info!("Received signal {}, draining the pool.", signal);
+
+            if let Some(shared) = shared.upgrade() {
+                drain(&shared);
+            }
+
+            if let Err(e) = low_level::emulate_default_handler(signal) {
+                warn!("Failed to re-raise signal {}: {}", signal, e);
+            }
+        })?;
+
+    Ok(())
+}
+
+/// Stops the pool from accepting jobs and waits until every job already
+/// submitted has run. The workers exit on their own once the queue is empty.
+fn drain(shared: &Shared) {
+    // Closing also lifts a pause, so queued jobs still run.
+    shared.queue.close();
+    shared.worker_freed();
+
+    let guard = lock(&shared.idle_lock);
+    let _guard = shared
+        .idle
+        .wait_while(guard, |_| shared.pending.load(Ordering::SeqCst) > 0)
+        .unwrap_or_else(PoisonError::into_inner);
+
+    info!("Pool drained.");
+}
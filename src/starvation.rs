@@ -0,0 +1,101 @@
+use std::{
+    sync::{Arc, PoisonError},
+    time::Duration,
+};
+
+use log::warn;
+
+use crate::{
+    lock,
+    sync::{atomic::Ordering, thread, Condvar, Mutex},
+    PoolEvent, Shared,
+};
+
+/// Monitor thread that warns when jobs wait in the queue while some workers
+/// are not running anything.
+///
+/// That usually means workers are blocked somewhere other than in a job, so
+/// the backlog is not being worked through. A paused pool is never reported.
+pub(crate) struct StarvationMonitor {
+    stop: Arc<(Mutex<bool>, Condvar)>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl StarvationMonitor {
+    pub(crate) fn new(shared: Arc<Shared>, interval: Duration) -> StarvationMonitor {
+        let stop = Arc::new((Mutex::new(false), Condvar::new()));
+
+        let thread = {
+            let stop = Arc::clone(&stop);
+
+            thread::Builder::new()
+                .name("yarws-starvation".to_string())
+                .spawn(move || run(&stop, &shared, interval))
+                .expect("failed to spawn starvation monitor thread")
+        };
+
+        StarvationMonitor {
+            stop,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for StarvationMonitor {
+    fn drop(&mut self) {
+        let (stopped, wakeup) = &*self.stop;
+
+        *lock(stopped) = true;
+        wakeup.notify_one();
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Returns the queue depth and active count if the pool looks starved right
+/// now.
+fn check(shared: &Shared) -> Option<(usize, usize)> {
+    if shared.queue.is_paused() {
+        return None;
+    }
+
+    let queued = shared.queued.load(Ordering::SeqCst);
+    let active = shared.active.load(Ordering::SeqCst);
+
+    (queued > 0 && active < shared.alive_workers()).then_some((queued, active))
+}
+
+fn run(stop: &(Mutex<bool>, Condvar), shared: &Shared, interval: Duration) {
+    let (stopped, wakeup) = stop;
+    // Starved at the previous check, and whether that spell was reported.
+    let mut starved = false;
+    let mut reported = false;
+    let mut guard = lock(stopped);
+
+    while !*guard {
+        guard = wakeup
+            .wait_timeout(guard, interval)
+            .unwrap_or_else(PoisonError::into_inner)
+            .0;
+
+        let Some((queued, active)) = check(shared) else {
+            starved = false;
+            reported = false;
+            continue;
+        };
+
+        // A single sighting can just be a job on its way to a worker.
+        if starved && !reported {
+            reported = true;
+
+            warn!(
+                "{} job(s) have been queued for {:?} while only {} worker(s) are running one.",
+                queued, interval, active
+            );
+            shared.emit(PoolEvent::QueueStarved { queued, active });
+        }
+        starved = true;
+    }
+}
@@ -0,0 +1,13 @@
+/// Lifetime counters for a `ThreadPool`, from `ThreadPool::stats`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PoolStats {
+    /// Jobs accepted into the queue.
+    pub total_submitted: u64,
+    /// Jobs that finished running, including ones that panicked. Jobs that
+    /// were skipped or abandoned never run, so they are not counted.
+    pub total_completed: u64,
+    /// Jobs that panicked.
+    pub total_panicked: u64,
+    /// The most jobs that have been running at the same time.
+    pub peak_active: usize,
+}
@@ -0,0 +1,11 @@
+/// What happened to the workers when a `ThreadPool` was joined.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShutdownSummary {
+    /// Number of workers that were still running and have now been joined.
+    pub workers_joined: usize,
+    /// Number of jobs still waiting in the queue when the shutdown began.
+    /// They all ran before the workers exited.
+    pub jobs_remaining_estimate: usize,
+    /// Ids of workers whose thread was ended by a panic.
+    pub panicked_workers: Vec<usize>,
+}
@@ -0,0 +1,160 @@
+//! The locks, atomics and threads the pool is built on.
+//!
+//! When built with `--cfg loom` these are swapped for loom's model-checked
+//! versions, so tests can explore every interleaving of the workers. Loom
+//! lacks a few `std` methods the pool relies on, which the wrappers below
+//! provide on top of what it has. `Arc`, `OnceLock` and channels always come
+//! from `std`, and a pool built under `cfg(loom)` only works inside `loom::model`.
+
+#[cfg(not(loom))]
+pub(crate) use std::{
+    sync::{atomic, Condvar, Mutex, MutexGuard, RwLock},
+    thread, thread_local,
+};
+
+#[cfg(loom)]
+pub(crate) use loom::{
+    sync::{atomic, Mutex, MutexGuard, RwLock},
+    thread_local,
+};
+
+#[cfg(loom)]
+pub(crate) use self::loom_condvar::Condvar;
+
+#[cfg(loom)]
+mod loom_condvar {
+    use std::{sync::LockResult, time::Duration};
+
+    use loom::sync::WaitTimeoutResult;
+
+    use super::MutexGuard;
+
+    /// `loom::sync::Condvar` with the `wait_while` the pool uses.
+    #[derive(Debug, Default)]
+    pub(crate) struct Condvar(loom::sync::Condvar);
+
+    impl Condvar {
+        pub(crate) fn new() -> Condvar {
+            Condvar(loom::sync::Condvar::new())
+        }
+
+        pub(crate) fn wait<'a, T>(
+            &self,
+            guard: MutexGuard<'a, T>,
+        ) -> LockResult<MutexGuard<'a, T>> {
+            self.0.wait(guard)
+        }
+
+        pub(crate) fn wait_while<'a, T, F>(
+            &self,
+            mut guard: MutexGuard<'a, T>,
+            mut condition: F,
+        ) -> LockResult<MutexGuard<'a, T>>
+        where
+            F: FnMut(&mut T) -> bool,
+        {
+            while condition(&mut *guard) {
+                guard = self.0.wait(guard)?;
+            }
+
+            Ok(guard)
+        }
+
+        /// Loom never times out, so this waits for a notification like
+        /// `wait`.
+        pub(crate) fn wait_timeout<'a, T>(
+            &self,
+            guard: MutexGuard<'a, T>,
+            dur: Duration,
+        ) -> LockResult<(MutexGuard<'a, T>, WaitTimeoutResult)> {
+            self.0.wait_timeout(guard, dur)
+        }
+
+        pub(crate) fn notify_one(&self) {
+            self.0.notify_one();
+        }
+
+        pub(crate) fn notify_all(&self) {
+            self.0.notify_all();
+        }
+    }
+}
+
+/// `loom::thread`, with a `JoinHandle` that can tell whether its thread has
+/// finished.
+#[cfg(loom)]
+pub(crate) mod thread {
+    use std::{
+        io,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+    };
+
+    pub(crate) use loom::thread::{current, park, yield_now, Thread, ThreadId};
+    pub(crate) use std::thread::{available_parallelism, sleep, Result};
+
+    #[derive(Debug)]
+    pub(crate) struct Builder(loom::thread::Builder);
+
+    impl Builder {
+        pub(crate) fn new() -> Builder {
+            Builder(loom::thread::Builder::new())
+        }
+
+        pub(crate) fn name(self, name: String) -> Builder {
+            Builder(self.0.name(name))
+        }
+
+        pub(crate) fn stack_size(self, size: usize) -> Builder {
+            Builder(self.0.stack_size(size))
+        }
+
+        pub(crate) fn spawn<F, T>(self, f: F) -> io::Result<JoinHandle<T>>
+        where
+            F: FnOnce() -> T + Send + 'static,
+            T: Send + 'static,
+        {
+            let finished = Arc::new(AtomicBool::new(false));
+            let flag = Finished(Arc::clone(&finished));
+
+            let inner = self.0.spawn(move || {
+                let _flag = flag;
+                f()
+            })?;
+
+            Ok(JoinHandle { inner, finished })
+        }
+    }
+
+    /// Sets the flag when dropped, so a panicking thread counts as finished
+    /// too.
+    struct Finished(Arc<AtomicBool>);
+
+    impl Drop for Finished {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[derive(Debug)]
+    pub(crate) struct JoinHandle<T> {
+        inner: loom::thread::JoinHandle<T>,
+        finished: Arc<AtomicBool>,
+    }
+
+    impl<T> JoinHandle<T> {
+        pub(crate) fn join(self) -> Result<T> {
+            self.inner.join()
+        }
+
+        pub(crate) fn thread(&self) -> &Thread {
+            self.inner.thread()
+        }
+
+        pub(crate) fn is_finished(&self) -> bool {
+            self.finished.load(Ordering::SeqCst)
+        }
+    }
+}
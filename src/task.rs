@@ -0,0 +1,177 @@
+use std::{
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use crate::{
+    lock,
+    sync::{Mutex, MutexGuard},
+};
+
+/// Size of a spilled task's header: its id and payload length.
+const HEADER_LEN: u64 = 4 + 8;
+
+/// Distinguishes the spill files of pools in the same process.
+static NEXT_SPILL: AtomicUsize = AtomicUsize::new(0);
+
+/// Identifies a kind of task registered with `ThreadPoolBuilder::task_handler`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TaskId(pub u32);
+
+/// A registered task handler, shown opaquely in `Debug` output.
+pub(crate) struct TaskHandler(pub(crate) Arc<dyn Fn(Vec<u8>) + Send + Sync>);
+
+impl fmt::Debug for TaskHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("TaskHandler")
+    }
+}
+
+/// Tasks written to disk while the in-memory queue is over its threshold,
+/// read back oldest first.
+pub(crate) struct Spill {
+    threshold: usize,
+    path: PathBuf,
+    /// Number of tasks on disk, so workers can skip the lock.
+    len: AtomicUsize,
+    inner: Mutex<SpillFile>,
+    /// Held while tasks move from the file to the queue, so the queue cannot
+    /// close with a task in neither.
+    reload: Mutex<()>,
+}
+
+struct SpillFile {
+    /// Created on the first spill.
+    file: Option<File>,
+    read_pos: u64,
+    write_pos: u64,
+}
+
+impl Spill {
+    pub(crate) fn new(threshold: usize, dir: &Path) -> Spill {
+        let name = format!(
+            "yarws-spill-{}-{}",
+            process::id(),
+            NEXT_SPILL.fetch_add(1, Ordering::Relaxed)
+        );
+
+        Spill {
+            threshold,
+            path: dir.join(name),
+            len: AtomicUsize::new(0),
+            inner: Mutex::new(SpillFile {
+                file: None,
+                read_pos: 0,
+                write_pos: 0,
+            }),
+            reload: Mutex::new(()),
+        }
+    }
+
+    pub(crate) fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len.load(Ordering::SeqCst)
+    }
+
+    /// Locks out every other caller that moves tasks back into the queue.
+    pub(crate) fn lock_reload(&self) -> MutexGuard<'_, ()> {
+        lock(&self.reload)
+    }
+
+    /// Appends a task to the file.
+    pub(crate) fn push(&self, task: TaskId, payload: &[u8]) -> io::Result<()> {
+        let mut inner = lock(&self.inner);
+        let SpillFile {
+            file, write_pos, ..
+        } = &mut *inner;
+
+        let file = match file {
+            Some(file) => file,
+            None => file.insert(
+                OpenOptions::new()
+                    .read(true)
+                    .write(true)
+                    .create(true)
+                    .truncate(true)
+                    .open(&self.path)?,
+            ),
+        };
+
+        file.seek(SeekFrom::Start(*write_pos))?;
+        file.write_all(&task.0.to_le_bytes())?;
+        file.write_all(&(payload.len() as u64).to_le_bytes())?;
+        file.write_all(payload)?;
+
+        *write_pos += HEADER_LEN + payload.len() as u64;
+        self.len.fetch_add(1, Ordering::SeqCst);
+
+        Ok(())
+    }
+
+    /// Takes the oldest task from the file, if there is one.
+    pub(crate) fn pop(&self) -> io::Result<Option<(TaskId, Vec<u8>)>> {
+        let mut inner = lock(&self.inner);
+        let SpillFile {
+            file,
+            read_pos,
+            write_pos,
+        } = &mut *inner;
+
+        let Some(file) = file.as_mut().filter(|_| read_pos < write_pos) else {
+            return Ok(None);
+        };
+
+        let mut header = [0; HEADER_LEN as usize];
+        file.seek(SeekFrom::Start(*read_pos))?;
+        file.read_exact(&mut header)?;
+
+        let task = TaskId(u32::from_le_bytes(header[..4].try_into().unwrap()));
+        let len = u64::from_le_bytes(header[4..].try_into().unwrap());
+        let mut payload = vec![0; len as usize];
+        file.read_exact(&mut payload)?;
+
+        *read_pos += HEADER_LEN + len;
+        self.len.fetch_sub(1, Ordering::SeqCst);
+
+        // Start over once everything has been read, so the file does not
+        // keep growing.
+        if read_pos == write_pos {
+            file.set_len(0)?;
+            *read_pos = 0;
+            *write_pos = 0;
+        }
+
+        Ok(Some((task, payload)))
+    }
+
+    /// Forgets every task on disk, returning how many there were.
+    pub(crate) fn clear(&self) -> usize {
+        let mut inner = lock(&self.inner);
+
+        if let Some(file) = &inner.file {
+            let _ = file.set_len(0);
+        }
+        inner.read_pos = 0;
+        inner.write_pos = 0;
+
+        self.len.swap(0, Ordering::SeqCst)
+    }
+}
+
+impl Drop for Spill {
+    fn drop(&mut self) {
+        if lock(&self.inner).file.take().is_some() {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+    sync::{Arc, PoisonError},
+    time::{Duration, Instant},
+};
+
+use log::debug;
+
+use crate::{
+    sync::{
+        atomic::{self, AtomicBool},
+        thread, Condvar, Mutex, MutexGuard,
+    },
+    Job, QueuedJob, Shared,
+};
+
+/// Holds delayed jobs and hands them to the pool's queue once they are due.
+///
+/// The timer runs on its own thread, which is only spawned for pools that
+/// actually schedule delayed jobs.
+pub(crate) struct Timer {
+    state: Arc<TimerState>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+pub(crate) struct TimerState {
+    inner: Mutex<Inner>,
+    wakeup: Condvar,
+}
+
+struct Inner {
+    entries: BinaryHeap<Reverse<Delayed>>,
+    next_seq: u64,
+    stopped: bool,
+}
+
+struct Delayed {
+    due: Instant,
+    seq: u64,
+    job: Job,
+}
+
+impl Ord for Delayed {
+    fn cmp(&self, other: &Delayed) -> Ordering {
+        self.due
+            .cmp(&other.due)
+            .then_with(|| self.seq.cmp(&other.seq))
+    }
+}
+
+impl PartialOrd for Delayed {
+    fn partial_cmp(&self, other: &Delayed) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Delayed {
+    fn eq(&self, other: &Delayed) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Delayed {}
+
+impl TimerState {
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Schedules `job` to be queued once `due` has passed.
+    ///
+    /// The job is dropped straight away if the timer has been stopped.
+    pub(crate) fn schedule(&self, due: Instant, job: Job) {
+        let mut inner = self.lock();
+
+        if inner.stopped {
+            return;
+        }
+
+        let seq = inner.next_seq;
+        inner.next_seq += 1;
+        inner.entries.push(Reverse(Delayed { due, seq, job }));
+
+        self.wakeup.notify_one();
+    }
+}
+
+impl Timer {
+    pub(crate) fn new(shared: Arc<Shared>) -> Timer {
+        let state = Arc::new(TimerState {
+            inner: Mutex::new(Inner {
+                entries: BinaryHeap::new(),
+                next_seq: 0,
+                stopped: false,
+            }),
+            wakeup: Condvar::new(),
+        });
+
+        let thread = {
+            let state = Arc::clone(&state);
+
+            thread::Builder::new()
+                .name("yarws-timer".to_string())
+                .spawn(move || run(&state, &shared))
+                .expect("failed to spawn timer thread")
+        };
+
+        Timer {
+            state,
+            thread: Some(thread),
+        }
+    }
+
+    pub(crate) fn state(&self) -> &Arc<TimerState> {
+        &self.state
+    }
+}
+
+impl Drop for Timer {
+    /// Stops the timer thread, dropping any jobs that are not yet due.
+    fn drop(&mut self) {
+        let dropped = {
+            let mut inner = self.state.lock();
+            inner.stopped = true;
+
+            let dropped = inner.entries.len();
+            inner.entries.clear();
+            dropped
+        };
+        self.state.wakeup.notify_one();
+
+        if dropped > 0 {
+            debug!("Dropping {} delayed job(s) that were not yet due.", dropped);
+        }
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Handle to a recurring job started with `ThreadPool::execute_every`.
+///
+/// The job stops recurring once `cancel` is called or the handle is dropped.
+/// A run that has already started is allowed to finish.
+#[derive(Debug)]
+pub struct TaskHandle {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl TaskHandle {
+    /// Stops the job from running again.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, atomic::Ordering::SeqCst);
+    }
+
+    /// Returns whether the job has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(atomic::Ordering::SeqCst)
+    }
+}
+
+impl Drop for TaskHandle {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// Schedules `f` to run every `interval`, starting one interval from now.
+pub(crate) fn schedule_every<F>(state: &Arc<TimerState>, interval: Duration, f: F) -> TaskHandle
+where
+    F: FnMut() + Send + 'static,
+{
+    let cancelled = Arc::new(AtomicBool::new(false));
+
+    recur(
+        Arc::clone(state),
+        Instant::now() + interval,
+        interval,
+        Arc::clone(&cancelled),
+        f,
+    );
+
+    TaskHandle { cancelled }
+}
+
+fn recur<F>(
+    state: Arc<TimerState>,
+    due: Instant,
+    interval: Duration,
+    cancelled: Arc<AtomicBool>,
+    mut f: F,
+) where
+    F: FnMut() + Send + 'static,
+{
+    let next = Arc::clone(&state);
+
+    state.schedule(
+        due,
+        Box::new(move || {
+            if cancelled.load(atomic::Ordering::SeqCst) {
+                return;
+            }
+
+            f();
+
+            if !cancelled.load(atomic::Ordering::SeqCst) {
+                recur(next, due + interval, interval, cancelled, f);
+            }
+        }),
+    );
+}
+
+fn run(state: &TimerState, shared: &Arc<Shared>) {
+    let mut inner = state.lock();
+
+    while !inner.stopped {
+        let now = Instant::now();
+
+        match inner.entries.peek() {
+            Some(Reverse(delayed)) if delayed.due <= now => {
+                let Reverse(delayed) = inner.entries.pop().unwrap();
+                drop(inner);
+
+                // The queue only refuses jobs once the pool is shutting down.
+                let _ = shared.submit(0, QueuedJob::new(delayed.job));
+
+                inner = state.lock();
+            }
+            Some(Reverse(delayed)) => {
+                let timeout = delayed.due - now;
+                inner = state
+                    .wakeup
+                    .wait_timeout(inner, timeout)
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .0;
+            }
+            None => {
+                inner = state
+                    .wakeup
+                    .wait(inner)
+                    .unwrap_or_else(PoisonError::into_inner);
+            }
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, PoisonError},
+    time::{Duration, Instant},
+};
+
+use log::warn;
+
+use crate::{
+    lock,
+    sync::{thread, Condvar, Mutex},
+    JobLabel, PoolEvent, Shared,
+};
+
+/// The job each worker is currently running, for spotting stuck jobs.
+#[derive(Default)]
+pub(crate) struct RunningJobs {
+    jobs: Mutex<HashMap<usize, RunningJob>>,
+}
+
+struct RunningJob {
+    started: Instant,
+    name: Option<String>,
+    warned: bool,
+}
+
+impl RunningJobs {
+    pub(crate) fn start(&self, worker: usize, name: &Option<String>) {
+        let job = RunningJob {
+            started: Instant::now(),
+            name: name.clone(),
+            warned: false,
+        };
+
+        lock(&self.jobs).insert(worker, job);
+    }
+
+    pub(crate) fn finish(&self, worker: usize) {
+        lock(&self.jobs).remove(&worker);
+    }
+}
+
+/// Monitor thread that warns about jobs running longer than a threshold.
+///
+/// Stuck jobs are only reported, once per job; they keep running.
+pub(crate) struct Watchdog {
+    stop: Arc<(Mutex<bool>, Condvar)>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl Watchdog {
+    pub(crate) fn new(shared: Arc<Shared>, threshold: Duration) -> Watchdog {
+        let stop = Arc::new((Mutex::new(false), Condvar::new()));
+
+        let thread = {
+            let stop = Arc::clone(&stop);
+
+            thread::Builder::new()
+                .name("yarws-watchdog".to_string())
+                .spawn(move || run(&stop, &shared, threshold))
+                .expect("failed to spawn watchdog thread")
+        };
+
+        Watchdog {
+            stop,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        let (stopped, wakeup) = &*self.stop;
+
+        *lock(stopped) = true;
+        wakeup.notify_one();
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn run(stop: &(Mutex<bool>, Condvar), shared: &Shared, threshold: Duration) {
+    let Some(running) = &shared.running else {
+        return;
+    };
+
+    let (stopped, wakeup) = stop;
+    let interval = (threshold / 4).max(Duration::from_millis(1));
+    let mut guard = lock(stopped);
+
+    while !*guard {
+        guard = wakeup
+            .wait_timeout(guard, interval)
+            .unwrap_or_else(PoisonError::into_inner)
+            .0;
+
+        let mut overdue = Vec::new();
+
+        for (&worker, job) in lock(&running.jobs).iter_mut() {
+            let elapsed = job.started.elapsed();
+
+            if !job.warned && elapsed > threshold {
+                job.warned = true;
+
+                warn!(
+                    "Worker {} has been running job{} for {:?}.",
+                    worker,
+                    JobLabel(&job.name),
+                    elapsed
+                );
+                overdue.push(PoolEvent::JobOverdue { worker, elapsed });
+            }
+        }
+
+        // Report outside the lock so the callback cannot stall workers.
+        for event in overdue {
+            shared.emit(event);
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! Tests of running futures on a pool and awaiting pool jobs.
+
+use std::{
+    future::Future,
+    pin::{pin, Pin},
+    sync::{mpsc, Arc},
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, Thread},
+};
+
+use yarws::{JobError, ThreadPool};
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Polls `future` to completion on the current thread.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+/// A future that is pending on its first poll and ready on its second.
+#[derive(Default)]
+struct YieldNow {
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.yielded {
+            return Poll::Ready(());
+        }
+        self.yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+#[test]
+fn spawned_future_resolves_after_yielding() {
+    let pool = ThreadPool::new(2).unwrap();
+
+    let future = pool.spawn(async {
+        YieldNow::default().await;
+        42
+    });
+
+    assert_eq!(block_on(future).unwrap(), 42);
+}
+
+#[test]
+fn execute_future_resolves_to_the_job_value() {
+    let pool = ThreadPool::new(2).unwrap();
+
+    let future = pool.execute_future(|| "done");
+
+    assert_eq!(block_on(future).unwrap(), "done");
+}
+
+#[test]
+fn future_of_a_cleared_job_resolves_to_dropped() {
+    let pool = ThreadPool::new(1).unwrap();
+    let (started_tx, started_rx) = mpsc::channel();
+    let (release_tx, release_rx) = mpsc::channel::<()>();
+
+    pool.execute(move || {
+        started_tx.send(()).unwrap();
+        release_rx.recv().unwrap();
+    });
+    started_rx.recv().unwrap();
+    let future = pool.execute_future(|| "never");
+    pool.clear_queue();
+    release_tx.send(()).unwrap();
+
+    assert!(matches!(block_on(future), Err(JobError::Dropped)));
+}
+
+#[test]
+fn future_of_a_panicking_job_resolves_to_panicked() {
+    let pool = ThreadPool::new(1).unwrap();
+
+    let future = pool.execute_future(|| -> u32 { panic!("boom") });
+
+    assert!(matches!(block_on(future), Err(JobError::Panicked(_))));
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn execute_async_waits_for_room_in_the_queue() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use yarws::ThreadPoolBuilder;
+
+    let pool = ThreadPoolBuilder::new()
+        .size(1)
+        .queue_capacity(2)
+        .build()
+        .unwrap();
+    let count = Arc::new(AtomicUsize::new(0));
+
+    block_on(async {
+        for _ in 0..20 {
+            let count = Arc::clone(&count);
+            pool.execute_async(move || {
+                count.fetch_add(1, Ordering::SeqCst);
+            })
+            .await;
+        }
+    });
+    pool.wait_for_idle();
+
+    assert_eq!(count.load(Ordering::SeqCst), 20);
+}
@@ -0,0 +1,84 @@
+//! Tests of skipping queued jobs that are no longer wanted.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use yarws::{CancellationToken, DeadlineOutcome, ThreadPool};
+
+#[test]
+fn job_cancelled_in_the_queue_never_runs() {
+    let pool = ThreadPool::new(1).unwrap();
+    let (release_tx, release_rx) = mpsc::channel::<()>();
+    let ran = Arc::new(AtomicBool::new(false));
+    let token = CancellationToken::new();
+
+    pool.execute(move || {
+        release_rx.recv().unwrap();
+    });
+    for _ in 0..100 {
+        pool.execute(|| {});
+    }
+    let flag = Arc::clone(&ran);
+    let outcome =
+        pool.execute_cancellable(token.clone(), move || flag.store(true, Ordering::SeqCst));
+    token.cancel();
+    release_tx.send(()).unwrap();
+
+    assert_eq!(outcome.recv(), Ok(false));
+    assert!(!ran.load(Ordering::SeqCst));
+}
+
+#[test]
+fn job_whose_deadline_passes_in_the_queue_is_skipped() {
+    let pool = ThreadPool::new(1).unwrap();
+
+    pool.execute(|| thread::sleep(Duration::from_millis(100)));
+    let outcome = pool.execute_with_deadline(Instant::now() + Duration::from_millis(10), |_| {
+        panic!("ran after its deadline")
+    });
+
+    assert_eq!(outcome.recv(), Ok(DeadlineOutcome::Skipped));
+}
+
+#[test]
+fn job_that_sees_its_deadline_reports_expired() {
+    let pool = ThreadPool::new(1).unwrap();
+
+    let outcome = pool.execute_with_deadline(Instant::now() + Duration::from_millis(10), |token| {
+        while !token.expired() {
+            thread::sleep(Duration::from_millis(1));
+        }
+    });
+
+    assert_eq!(outcome.recv(), Ok(DeadlineOutcome::Expired));
+}
+
+#[test]
+fn cancelling_a_group_skips_its_queued_jobs() {
+    let pool = ThreadPool::new(1).unwrap();
+    let group = pool.group();
+    let (started_tx, started_rx) = mpsc::channel();
+    let ran = Arc::new(AtomicUsize::new(0));
+
+    for _ in 0..10 {
+        let started_tx = started_tx.clone();
+        let ran = Arc::clone(&ran);
+        group.execute(move || {
+            started_tx.send(()).unwrap();
+            ran.fetch_add(1, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(20));
+        });
+    }
+    started_rx.recv().unwrap();
+    group.cancel_all();
+    pool.wait_for_idle();
+
+    assert_eq!(ran.load(Ordering::SeqCst), 1);
+    assert!(group.is_cancelled());
+}
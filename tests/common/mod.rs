@@ -0,0 +1,16 @@
+//! Helpers shared by the integration tests.
+
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Polls `condition` until it holds, failing the test after five seconds.
+pub fn wait_until(mut condition: impl FnMut() -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+
+    while !condition() {
+        assert!(Instant::now() < deadline, "condition never held");
+        thread::sleep(Duration::from_millis(1));
+    }
+}
@@ -0,0 +1,58 @@
+//! Tests of pools that keep their jobs in a user-supplied queue.
+
+use std::sync::{Arc, Mutex};
+
+use yarws::{CustomQueue, PendingJob, StdQueue, ThreadPoolBuilder};
+
+type Log = Arc<Mutex<Vec<u32>>>;
+
+/// Hands out the job pushed last first.
+#[derive(Default)]
+struct Stack(Mutex<Vec<PendingJob>>);
+
+impl CustomQueue for Stack {
+    fn push(&self, job: PendingJob) {
+        self.0.lock().unwrap().push(job);
+    }
+
+    fn pop(&self) -> Option<PendingJob> {
+        self.0.lock().unwrap().pop()
+    }
+}
+
+/// Runs the jobs `(priority, label)` on a one-worker pool using `queue`,
+/// all queued before any starts, and returns the labels in the order they
+/// ran.
+fn run_order(queue: impl CustomQueue + 'static, jobs: &[(u8, u32)]) -> Vec<u32> {
+    let pool = ThreadPoolBuilder::new()
+        .size(1)
+        .custom_queue(queue)
+        .build()
+        .unwrap();
+    let log = Log::default();
+
+    pool.pause();
+    for &(priority, label) in jobs {
+        let log = Arc::clone(&log);
+        pool.execute_with_priority(priority, move || log.lock().unwrap().push(label));
+    }
+    pool.resume();
+    pool.wait_for_idle();
+
+    let order = log.lock().unwrap().clone();
+    order
+}
+
+#[test]
+fn custom_queue_decides_the_order() {
+    let order = run_order(Stack::default(), &[(0, 1), (0, 2), (0, 3), (0, 4)]);
+
+    assert_eq!(order, [4, 3, 2, 1]);
+}
+
+#[test]
+fn std_queue_runs_by_priority_then_submission_order() {
+    let order = run_order(StdQueue::new(), &[(0, 1), (2, 2), (0, 3), (2, 4)]);
+
+    assert_eq!(order, [2, 4, 1, 3]);
+}
@@ -0,0 +1,295 @@
+//! Tests of the ways jobs are submitted to a pool.
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use yarws::{ExecuteError, ThreadPool, ThreadPoolBuilder};
+
+#[test]
+fn execute_with_result_returns_each_value() {
+    let pool = ThreadPool::new(4).unwrap();
+
+    let receivers: Vec<_> = (0..10)
+        .map(|i| pool.execute_with_result(move || i * i))
+        .collect();
+    let squares: Vec<i32> = receivers.iter().map(|rx| rx.recv().unwrap()).collect();
+
+    assert_eq!(squares, [0, 1, 4, 9, 16, 25, 36, 49, 64, 81]);
+}
+
+#[test]
+fn try_execute_fails_once_the_queue_is_full() {
+    let pool = ThreadPoolBuilder::new()
+        .size(1)
+        .queue_capacity(2)
+        .build()
+        .unwrap();
+    let (started_tx, started_rx) = mpsc::channel();
+
+    pool.execute(move || {
+        started_tx.send(()).unwrap();
+        thread::sleep(Duration::from_millis(200));
+    });
+    started_rx.recv().unwrap();
+
+    for _ in 0..2 {
+        pool.try_execute(|| thread::sleep(Duration::from_millis(10)))
+            .unwrap();
+    }
+
+    assert_eq!(pool.try_execute(|| {}), Err(ExecuteError::Full));
+}
+
+#[test]
+fn wait_for_idle_waits_for_every_job() {
+    let pool = ThreadPool::new(4).unwrap();
+    let counter = Arc::new(AtomicUsize::new(0));
+
+    for _ in 0..20 {
+        let counter = Arc::clone(&counter);
+        pool.execute(move || {
+            thread::sleep(Duration::from_millis(5));
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+    }
+    pool.wait_for_idle();
+
+    assert_eq!(counter.load(Ordering::SeqCst), 20);
+}
+
+#[test]
+fn execute_all_runs_every_job() {
+    let pool = ThreadPool::new(4).unwrap();
+    let count = Arc::new(AtomicUsize::new(0));
+
+    let jobs: Vec<_> = (0..50)
+        .map(|_| {
+            let count = Arc::clone(&count);
+            move || {
+                count.fetch_add(1, Ordering::SeqCst);
+            }
+        })
+        .collect();
+    pool.execute_all(jobs);
+    pool.wait_for_idle();
+
+    assert_eq!(count.load(Ordering::SeqCst), 50);
+}
+
+#[test]
+fn execute_mut_keeps_the_closure_state() {
+    let pool = ThreadPool::new(2).unwrap();
+    let (tx, rx) = mpsc::channel();
+    let mut count = 0;
+    let mut job = move || {
+        count += 1;
+        tx.send(count).unwrap();
+    };
+
+    for _ in 0..3 {
+        job = pool.execute_mut(job).join().unwrap();
+    }
+
+    assert_eq!(rx.try_iter().collect::<Vec<_>>(), [1, 2, 3]);
+}
+
+#[test]
+fn execute_boxed_runs_the_job() {
+    let pool = ThreadPool::new(1).unwrap();
+    let (tx, rx) = mpsc::channel();
+
+    let job: Box<dyn FnOnce() + Send + 'static> = Box::new(move || tx.send("ran").unwrap());
+    pool.execute_boxed(job);
+
+    assert_eq!(rx.recv(), Ok("ran"));
+}
+
+#[test]
+fn dropped_result_receivers_do_not_stop_the_jobs() {
+    let pool = ThreadPool::new(4).unwrap();
+    let count = Arc::new(AtomicUsize::new(0));
+
+    for i in 0..1000 {
+        let count = Arc::clone(&count);
+        drop(pool.execute_with_result(move || {
+            count.fetch_add(1, Ordering::SeqCst);
+            i
+        }));
+    }
+    pool.wait_for_idle();
+
+    assert_eq!(count.load(Ordering::SeqCst), 1000);
+    assert_eq!(pool.stats().total_panicked, 0);
+    assert_eq!(pool.size(), 4);
+}
+
+#[test]
+fn jobs_can_submit_jobs_to_a_full_pool() {
+    let pool = ThreadPoolBuilder::new()
+        .size(4)
+        .queue_capacity(2)
+        .build()
+        .unwrap();
+    let count = Arc::new(AtomicUsize::new(0));
+
+    for _ in 0..4 {
+        let inner = pool.handle();
+        let count = Arc::clone(&count);
+        pool.execute(move || {
+            count.fetch_add(1, Ordering::SeqCst);
+            for _ in 0..4 {
+                let count = Arc::clone(&count);
+                inner.execute(move || {
+                    count.fetch_add(1, Ordering::SeqCst);
+                });
+            }
+        });
+    }
+    pool.wait_for_idle();
+
+    assert_eq!(count.load(Ordering::SeqCst), 20);
+}
+
+/// Shared state handed to jobs through the pool's context.
+struct Settings {
+    step: usize,
+    total: AtomicUsize,
+}
+
+#[test]
+fn jobs_read_and_update_the_pool_context() {
+    let pool = ThreadPoolBuilder::new()
+        .size(4)
+        .context(Settings {
+            step: 3,
+            total: AtomicUsize::new(0),
+        })
+        .build()
+        .unwrap();
+
+    for _ in 0..10 {
+        pool.execute_with_ctx(|settings: &Settings| {
+            settings.total.fetch_add(settings.step, Ordering::SeqCst);
+        });
+    }
+    pool.wait_for_idle();
+
+    let settings = pool.context::<Settings>().unwrap();
+    assert_eq!(settings.total.load(Ordering::SeqCst), 30);
+}
+
+#[test]
+fn execute_with_retry_succeeds_on_the_third_attempt() {
+    let pool = ThreadPool::new(1).unwrap();
+    let runs = Arc::new(AtomicUsize::new(0));
+
+    let counter = Arc::clone(&runs);
+    let outcome = pool.execute_with_retry(3, move || {
+        if counter.fetch_add(1, Ordering::SeqCst) < 2 {
+            Err("not yet")
+        } else {
+            Ok(())
+        }
+    });
+
+    assert_eq!(outcome.recv(), Ok(Ok(())));
+    assert_eq!(runs.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn execute_when_free_blocks_while_every_worker_is_busy() {
+    let pool = ThreadPool::new(1).unwrap();
+    let (started_tx, started_rx) = mpsc::channel();
+
+    pool.execute(move || {
+        started_tx.send(()).unwrap();
+        thread::sleep(Duration::from_millis(200));
+    });
+    started_rx.recv().unwrap();
+
+    let start = Instant::now();
+    pool.execute_when_free(|| {});
+
+    assert!(start.elapsed() >= Duration::from_millis(100));
+    pool.wait_for_idle();
+}
+
+#[test]
+fn failed_jobs_report_on_the_error_channel() {
+    let pool = ThreadPool::new(2).unwrap();
+    let errors = pool.errors::<String>();
+
+    for i in 0..5 {
+        pool.execute_fallible(move || {
+            if i % 2 == 0 {
+                Err(format!("job {} failed", i))
+            } else {
+                Ok(())
+            }
+        });
+    }
+    pool.wait_for_idle();
+
+    let mut received: Vec<_> = errors.try_iter().collect();
+    received.sort();
+    assert_eq!(received, ["job 0 failed", "job 2 failed", "job 4 failed"]);
+}
+
+#[test]
+fn execute_or_run_runs_inline_when_saturated() {
+    let pool = ThreadPoolBuilder::new()
+        .size(2)
+        .inline_threshold(2)
+        .build()
+        .unwrap();
+    let caller = thread::current().id();
+    let inline = Arc::new(AtomicUsize::new(0));
+
+    for _ in 0..20 {
+        let inline = Arc::clone(&inline);
+        pool.execute_or_run(move || {
+            if thread::current().id() == caller {
+                inline.fetch_add(1, Ordering::SeqCst);
+            }
+            thread::sleep(Duration::from_millis(10));
+        });
+    }
+    pool.wait_for_idle();
+
+    assert!(inline.load(Ordering::SeqCst) > 0);
+}
+
+#[test]
+fn execute_on_runs_every_pinned_job_on_that_worker() {
+    let pool = ThreadPool::new(4).unwrap();
+    let (tx, rx) = mpsc::channel();
+
+    for _ in 0..5 {
+        let tx = tx.clone();
+        pool.execute_on(2, move || tx.send(thread::current().id()).unwrap())
+            .unwrap();
+    }
+    drop(tx);
+    let threads: Vec<_> = rx.iter().collect();
+
+    let worker = pool
+        .worker_info()
+        .into_iter()
+        .find(|worker| worker.id == 2)
+        .unwrap();
+    assert_eq!(threads.len(), 5);
+    assert!(threads.iter().all(|&id| id == worker.thread_id));
+}
+
+#[test]
+fn execute_on_rejects_an_unknown_worker() {
+    let pool = ThreadPool::new(4).unwrap();
+
+    assert_eq!(pool.execute_on(4, || {}), Err(ExecuteError::NoSuchWorker));
+}
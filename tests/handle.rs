@@ -0,0 +1,143 @@
+//! Tests of job handles and of handles to the pool itself.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use yarws::{JobError, ThreadPool, TryJoinError};
+
+#[test]
+fn join_returns_the_job_value() {
+    let pool = ThreadPool::new(2).unwrap();
+
+    let handle = pool.execute_with_handle(|| 6 * 7);
+
+    assert_eq!(handle.join().unwrap(), 42);
+}
+
+#[test]
+fn join_surfaces_a_panic() {
+    let pool = ThreadPool::new(2).unwrap();
+
+    let handle = pool.execute_with_handle(|| -> i32 { panic!("boom") });
+
+    match handle.join() {
+        Err(JobError::Panicked(payload)) => {
+            assert_eq!(payload.downcast_ref::<&str>(), Some(&"boom"));
+        }
+        other => panic!("expected a panic, got {:?}", other),
+    }
+}
+
+#[test]
+fn try_join_timeout_can_be_retried() {
+    let pool = ThreadPool::new(1).unwrap();
+
+    let handle = pool.execute_with_handle(|| {
+        thread::sleep(Duration::from_millis(200));
+        "done"
+    });
+
+    assert!(matches!(
+        handle.try_join_timeout(Duration::from_millis(10)),
+        Err(TryJoinError::Timeout)
+    ));
+    assert!(matches!(
+        handle.try_join_timeout(Duration::from_secs(5)),
+        Ok("done")
+    ));
+}
+
+#[test]
+fn dropping_a_cancel_on_drop_handle_cancels_a_queued_job() {
+    let pool = ThreadPool::new(1).unwrap();
+    let (started_tx, started_rx) = mpsc::channel();
+    let (release_tx, release_rx) = mpsc::channel::<()>();
+    let ran = Arc::new(AtomicBool::new(false));
+
+    pool.execute(move || {
+        started_tx.send(()).unwrap();
+        release_rx.recv().unwrap();
+    });
+    started_rx.recv().unwrap();
+    let queued = {
+        let ran = Arc::clone(&ran);
+        pool.execute_with_handle(move || ran.store(true, Ordering::SeqCst))
+    };
+    drop(queued.cancel_on_drop());
+    release_tx.send(()).unwrap();
+    pool.wait_for_idle();
+
+    assert!(!ran.load(Ordering::SeqCst));
+}
+
+#[test]
+fn dropping_a_plain_handle_leaves_the_job_queued() {
+    let pool = ThreadPool::new(1).unwrap();
+    let ran = Arc::new(AtomicBool::new(false));
+
+    pool.pause();
+    let queued = {
+        let ran = Arc::clone(&ran);
+        pool.execute_with_handle(move || ran.store(true, Ordering::SeqCst))
+    };
+    drop(queued);
+    pool.resume();
+    pool.wait_for_idle();
+
+    assert!(ran.load(Ordering::SeqCst));
+}
+
+#[test]
+fn cloned_pool_handles_submit_to_the_same_workers() {
+    let pool = ThreadPool::new(2).unwrap();
+    let count = Arc::new(AtomicUsize::new(0));
+
+    let submitters: Vec<_> = (0..2)
+        .map(|_| {
+            let handle = pool.handle().clone();
+            let count = Arc::clone(&count);
+            thread::spawn(move || {
+                for _ in 0..100 {
+                    let count = Arc::clone(&count);
+                    handle.execute(move || {
+                        count.fetch_add(1, Ordering::SeqCst);
+                    });
+                }
+            })
+        })
+        .collect();
+    for submitter in submitters {
+        submitter.join().unwrap();
+    }
+    pool.wait_for_idle();
+
+    assert_eq!(count.load(Ordering::SeqCst), 200);
+    assert_eq!(pool.size(), 2);
+    assert!(pool.is_healthy());
+}
+
+#[test]
+fn cloned_sender_submits_from_another_thread() {
+    let pool = ThreadPool::new(2).unwrap();
+    let sender = pool.sender().clone();
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || sender.execute(move || tx.send("ran").unwrap()))
+        .join()
+        .unwrap();
+
+    assert_eq!(rx.recv_timeout(Duration::from_secs(5)), Ok("ran"));
+}
+
+#[test]
+fn spawn_blocking_joins_to_the_result() {
+    let pool = ThreadPool::new(2).unwrap();
+
+    assert_eq!(pool.spawn_blocking(|| 21 * 2).join().unwrap(), 42);
+}
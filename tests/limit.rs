@@ -0,0 +1,128 @@
+//! Tests of the limits a pool can put on how jobs run.
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use yarws::{SemaphoreKey, ThreadPoolBuilder};
+
+/// Tracks how many jobs run at once and the most that ever did.
+#[derive(Default)]
+struct Gauge {
+    running: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+impl Gauge {
+    /// Counts as running for a few milliseconds.
+    fn run(&self, amount: usize) {
+        let now = self.running.fetch_add(amount, Ordering::SeqCst) + amount;
+        self.peak.fetch_max(now, Ordering::SeqCst);
+        thread::sleep(Duration::from_millis(10));
+        self.running.fetch_sub(amount, Ordering::SeqCst);
+    }
+
+    fn peak(&self) -> usize {
+        self.peak.load(Ordering::SeqCst)
+    }
+}
+
+#[test]
+fn rate_limit_spreads_jobs_over_time() {
+    let pool = ThreadPoolBuilder::new()
+        .size(4)
+        .max_jobs_per_second(5)
+        .build()
+        .unwrap();
+    let start = Instant::now();
+
+    for _ in 0..10 {
+        pool.execute(|| {});
+    }
+    pool.wait_for_idle();
+
+    // The first five start at once; the rest take another second.
+    assert!(start.elapsed() >= Duration::from_millis(900));
+}
+
+#[test]
+fn concurrency_limit_caps_jobs_of_a_key() {
+    let pool = ThreadPoolBuilder::new()
+        .size(8)
+        .concurrency_limit("db", 2)
+        .build()
+        .unwrap();
+    let gauge = Arc::new(Gauge::default());
+
+    for _ in 0..32 {
+        let gauge = Arc::clone(&gauge);
+        pool.execute_limited(SemaphoreKey::new("db"), move || gauge.run(1));
+    }
+    pool.wait_for_idle();
+
+    assert_eq!(gauge.peak(), 2);
+}
+
+#[test]
+fn heavy_job_excludes_every_other_job() {
+    let pool = ThreadPoolBuilder::new()
+        .size(8)
+        .max_concurrent_weight(4)
+        .build()
+        .unwrap();
+    let gauge = Arc::new(Gauge::default());
+    let alone = Arc::new(AtomicUsize::new(0));
+
+    for i in 0..40 {
+        let gauge = Arc::clone(&gauge);
+        if i % 10 == 5 {
+            let alone = Arc::clone(&alone);
+            pool.execute_weighted(4, move || {
+                if gauge.running.load(Ordering::SeqCst) == 0 {
+                    alone.fetch_add(1, Ordering::SeqCst);
+                }
+                gauge.run(4);
+            });
+        } else {
+            pool.execute_weighted(1, move || gauge.run(1));
+        }
+    }
+    pool.wait_for_idle();
+
+    assert_eq!(gauge.peak(), 4);
+    assert_eq!(alone.load(Ordering::SeqCst), 4);
+}
+
+#[test]
+fn light_jobs_do_not_overtake_a_waiting_heavy_job() {
+    let pool = ThreadPoolBuilder::new()
+        .size(4)
+        .max_concurrent_weight(2)
+        .build()
+        .unwrap();
+    let started = Arc::new(Mutex::new(Vec::new()));
+    let record = |label: &'static str| {
+        let started = Arc::clone(&started);
+        move || {
+            started.lock().unwrap().push(label);
+            thread::sleep(Duration::from_millis(20));
+        }
+    };
+
+    pool.execute_weighted(1, record("light"));
+    thread::sleep(Duration::from_millis(10));
+    pool.execute_weighted(2, record("heavy"));
+    thread::sleep(Duration::from_millis(10));
+    for _ in 0..6 {
+        pool.execute_weighted(1, record("light"));
+    }
+    pool.wait_for_idle();
+
+    let started = started.lock().unwrap();
+    assert_eq!(started[..2], ["light", "heavy"]);
+}
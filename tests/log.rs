@@ -0,0 +1,72 @@
+//! Tests that the pool reports its lifecycle through the `log` crate.
+
+use std::sync::{Mutex, Once};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use yarws::ThreadPool;
+
+/// Records every message logged by the pool.
+struct Recorder(Mutex<Vec<(Level, String)>>);
+
+impl Log for Recorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if record.target().starts_with("yarws") {
+            let message = record.args().to_string();
+            self.0.lock().unwrap().push((record.level(), message));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+impl Recorder {
+    /// Returns whether a message containing `text` has been logged at
+    /// `level`.
+    fn logged(&self, level: Level, text: &str) -> bool {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|(l, message)| *l == level && message.contains(text))
+    }
+}
+
+/// Returns the recorder, installing it as the logger on first use.
+fn recorder() -> &'static Recorder {
+    static RECORDER: Recorder = Recorder(Mutex::new(Vec::new()));
+    static INSTALL: Once = Once::new();
+
+    INSTALL.call_once(|| {
+        log::set_logger(&RECORDER).unwrap();
+        log::set_max_level(LevelFilter::Trace);
+    });
+
+    &RECORDER
+}
+
+#[test]
+fn job_pickup_is_traced_and_shutdown_is_info() {
+    let recorder = recorder();
+
+    let pool = ThreadPool::new(1).unwrap();
+    pool.execute(|| {});
+    drop(pool);
+
+    assert!(recorder.logged(Level::Trace, "got a job"));
+    assert!(recorder.logged(Level::Info, "shutting down"));
+}
+
+#[test]
+fn job_names_appear_in_the_log() {
+    let recorder = recorder();
+
+    let pool = ThreadPool::new(1).unwrap();
+    pool.execute_named("resize-thumbnail", || {});
+    pool.wait_for_idle();
+
+    assert!(recorder.logged(Level::Trace, "got a job 'resize-thumbnail'"));
+}
@@ -0,0 +1,113 @@
+//! Model checks of the pool under loom. Run with
+//! `RUSTFLAGS="--cfg loom" cargo test --release --test loom`.
+
+#![cfg(loom)]
+
+use std::{
+    env,
+    panic::{self, AssertUnwindSafe},
+};
+
+use loom::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+};
+use yarws::{TaskId, ThreadPool, ThreadPoolBuilder};
+
+#[test]
+fn shutdown_runs_every_queued_job() {
+    // A worker spins while a push is half done, which blows up the search
+    // past one preemption.
+    let mut model = loom::model::Builder::new();
+    model.preemption_bound = Some(1);
+    model.max_branches = 10_000;
+
+    model.check(|| {
+        let pool = ThreadPool::new(2).unwrap();
+        let ran = Arc::new(AtomicUsize::new(0));
+
+        for _ in 0..2 {
+            let ran = Arc::clone(&ran);
+
+            pool.execute(move || {
+                ran.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+        drop(pool);
+
+        assert_eq!(ran.load(Ordering::SeqCst), 2);
+    });
+}
+
+#[test]
+fn task_spilled_during_shutdown_runs_or_is_rejected() {
+    let mut model = loom::model::Builder::new();
+    model.preemption_bound = Some(2);
+    model.max_branches = 10_000;
+
+    model.check(|| {
+        let ran = Arc::new(AtomicUsize::new(0));
+        let pool = {
+            let ran = Arc::clone(&ran);
+
+            ThreadPoolBuilder::new()
+                .size(1)
+                .spill_to_disk(1, env::temp_dir())
+                .task_handler(
+                    TaskId(1),
+                    Box::new(move |_| {
+                        ran.fetch_add(1, Ordering::SeqCst);
+                    }),
+                )
+                .build()
+                .unwrap()
+        };
+
+        // Paused, the first task stays queued so the second one spills.
+        pool.pause();
+        pool.execute_task(TaskId(1), Vec::new());
+        let handle = pool.handle();
+        let submitter = thread::spawn(move || {
+            panic::catch_unwind(AssertUnwindSafe(|| {
+                handle.execute_task(TaskId(1), Vec::new());
+            }))
+            .is_ok()
+        });
+        drop(pool);
+        let accepted = submitter.join().unwrap();
+
+        assert_eq!(ran.load(Ordering::SeqCst), 1 + usize::from(accepted));
+    });
+}
+
+#[test]
+fn job_submitted_during_shutdown_runs_or_is_rejected() {
+    let mut model = loom::model::Builder::new();
+    model.preemption_bound = Some(2);
+    model.max_branches = 10_000;
+
+    model.check(|| {
+        let ran = Arc::new(AtomicUsize::new(0));
+        let pool = ThreadPool::new(1).unwrap();
+        let handle = pool.handle();
+        let submitter = {
+            let ran = Arc::clone(&ran);
+
+            thread::spawn(move || {
+                panic::catch_unwind(AssertUnwindSafe(|| {
+                    handle.execute(move || {
+                        ran.fetch_add(1, Ordering::SeqCst);
+                    });
+                }))
+                .is_ok()
+            })
+        };
+        drop(pool);
+        let accepted = submitter.join().unwrap();
+
+        assert_eq!(ran.load(Ordering::SeqCst), usize::from(accepted));
+    });
+}
@@ -0,0 +1,80 @@
+//! Tests of single-stepping a pool built with `ThreadPoolBuilder::manual`.
+//! Run with `cargo test --features manual --test manual`.
+
+#![cfg(feature = "manual")]
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use yarws::ThreadPoolBuilder;
+
+#[test]
+fn each_step_runs_one_job() {
+    let pool = ThreadPoolBuilder::new().manual(true).build().unwrap();
+    let count = Arc::new(AtomicUsize::new(0));
+
+    for _ in 0..3 {
+        let count = Arc::clone(&count);
+        pool.execute(move || {
+            count.fetch_add(1, Ordering::SeqCst);
+        });
+    }
+    assert_eq!(count.load(Ordering::SeqCst), 0);
+
+    assert!(pool.step());
+    assert!(pool.step());
+    assert_eq!(count.load(Ordering::SeqCst), 2);
+
+    assert!(pool.step());
+    assert_eq!(count.load(Ordering::SeqCst), 3);
+    assert!(!pool.step());
+}
+
+#[test]
+fn restarted_manual_pool_still_waits_for_step() {
+    let mut pool = ThreadPoolBuilder::new().manual(true).build().unwrap();
+    let count = Arc::new(AtomicUsize::new(0));
+
+    pool.restart().unwrap();
+    {
+        let count = Arc::clone(&count);
+        pool.execute(move || {
+            count.fetch_add(1, Ordering::SeqCst);
+        });
+    }
+    thread::sleep(Duration::from_millis(50));
+
+    assert_eq!(pool.size(), 0);
+    assert_eq!(count.load(Ordering::SeqCst), 0);
+    assert!(pool.step());
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn step_returns_false_only_once_the_queue_is_empty() {
+    let pool = ThreadPoolBuilder::new().manual(true).build().unwrap();
+    let order = Arc::new(Mutex::new(Vec::new()));
+
+    for (priority, name) in [(0, "low"), (9, "high"), (5, "middle")] {
+        let order = Arc::clone(&order);
+        pool.execute_with_priority(priority, move || {
+            order.lock().unwrap().push(name);
+        });
+    }
+    pool.execute(|| panic!("step keeps going after a panic"));
+
+    let mut steps = 0;
+    while pool.step() {
+        steps += 1;
+    }
+
+    assert_eq!(steps, 4);
+    assert_eq!(*order.lock().unwrap(), ["high", "middle", "low"]);
+    assert!(!pool.step());
+}
@@ -0,0 +1,212 @@
+//! Tests of the counters and snapshots a pool exposes.
+
+mod common;
+
+use std::{
+    sync::{mpsc, Arc, Barrier, Mutex},
+    thread,
+    time::Duration,
+};
+
+use yarws::{PoolEvent, ThreadPool, ThreadPoolBuilder};
+
+use common::wait_until;
+
+#[test]
+fn active_count_rises_and_falls() {
+    let pool = ThreadPool::new(4).unwrap();
+    let release = Arc::new(Barrier::new(4));
+
+    assert_eq!(pool.size(), 4);
+    assert_eq!(pool.active_count(), 0);
+
+    for _ in 0..3 {
+        let release = Arc::clone(&release);
+        pool.execute(move || {
+            release.wait();
+        });
+    }
+    wait_until(|| pool.active_count() == 3);
+
+    release.wait();
+    pool.wait_for_idle();
+
+    assert_eq!(pool.active_count(), 0);
+}
+
+#[test]
+fn events_pair_each_start_with_a_completion() {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&events);
+    let pool = ThreadPoolBuilder::new()
+        .size(1)
+        .on_event(Box::new(move |event| sink.lock().unwrap().push(event)))
+        .build()
+        .unwrap();
+
+    pool.execute(|| {});
+    pool.wait_for_idle();
+    wait_until(|| {
+        events
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|event| matches!(event, PoolEvent::JobCompleted { .. }))
+    });
+
+    let events = events.lock().unwrap();
+    let started = events
+        .iter()
+        .position(|event| matches!(event, PoolEvent::JobStarted { worker: 0 }));
+    let completed = events
+        .iter()
+        .position(|event| matches!(event, PoolEvent::JobCompleted { worker: 0, .. }));
+    assert!(events.contains(&PoolEvent::JobEnqueued));
+    assert!(started.unwrap() < completed.unwrap());
+}
+
+#[test]
+fn queued_count_drains_as_jobs_are_picked_up() {
+    let pool = ThreadPool::new(1).unwrap();
+
+    for _ in 0..10 {
+        pool.execute(|| thread::sleep(Duration::from_millis(20)));
+    }
+    let before = pool.queued_count();
+    assert!(before > 0 && before <= 10);
+    wait_until(|| pool.queued_count() < before);
+
+    pool.wait_for_idle();
+    assert_eq!(pool.queued_count(), 0);
+}
+
+#[test]
+fn worker_info_lists_every_live_worker() {
+    let pool = ThreadPool::new(4).unwrap();
+
+    let info = pool.worker_info();
+
+    assert_eq!(info.len(), pool.size());
+    assert!(info.iter().all(|worker| worker.alive));
+}
+
+#[test]
+fn overdue_job_emits_a_warning_event() {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&events);
+    let pool = ThreadPoolBuilder::new()
+        .size(1)
+        .job_timeout_warning(Duration::from_millis(20))
+        .on_event(Box::new(move |event| sink.lock().unwrap().push(event)))
+        .build()
+        .unwrap();
+
+    pool.execute(|| thread::sleep(Duration::from_millis(200)));
+
+    wait_until(|| {
+        events
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|event| matches!(event, PoolEvent::JobOverdue { worker: 0, .. }))
+    });
+}
+
+#[test]
+fn stats_count_every_submitted_and_completed_job() {
+    let pool = ThreadPool::new(4).unwrap();
+
+    for _ in 0..30 {
+        pool.execute(|| {});
+    }
+    pool.wait_for_idle();
+
+    let stats = pool.stats();
+    assert_eq!(stats.total_submitted, 30);
+    assert_eq!(stats.total_completed, 30);
+    assert_eq!(stats.total_panicked, 0);
+    assert!(stats.peak_active >= 1 && stats.peak_active <= 4);
+}
+
+#[test]
+fn wait_for_completions_waits_for_the_batch() {
+    let pool = ThreadPool::new(4).unwrap();
+    let results = Arc::new(Mutex::new(Vec::new()));
+
+    // Held back until the wait has begun, since only later completions
+    // count.
+    pool.pause();
+    for i in 0..10 {
+        let results = Arc::clone(&results);
+        pool.execute(move || results.lock().unwrap().push(i));
+    }
+    let handle = pool.handle();
+    let resumer = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(20));
+        handle.resume();
+    });
+    pool.wait_for_completions(10);
+
+    assert_eq!(results.lock().unwrap().len(), 10);
+    resumer.join().unwrap();
+}
+
+#[test]
+fn blocked_worker_emits_a_starvation_event() {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&events);
+    let pool = ThreadPoolBuilder::new()
+        .size(2)
+        .starvation_check(Duration::from_millis(10))
+        .on_event(Box::new(move |event| sink.lock().unwrap().push(event)))
+        .build()
+        .unwrap();
+    let (release_tx, release_rx) = mpsc::channel::<()>();
+
+    // Pinned to worker 0, so the queued job waits behind the blocked one
+    // while worker 1 has nothing to run.
+    pool.execute_on(0, move || release_rx.recv().unwrap())
+        .unwrap();
+    pool.execute_on(0, || {}).unwrap();
+
+    wait_until(|| {
+        events
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|event| matches!(event, PoolEvent::QueueStarved { .. }))
+    });
+    release_tx.send(()).unwrap();
+}
+
+#[test]
+fn current_jobs_shows_a_running_named_job() {
+    let pool = ThreadPool::new(2).unwrap();
+    let (release_tx, release_rx) = mpsc::channel::<()>();
+
+    pool.execute_named("nightly-report", move || release_rx.recv().unwrap());
+    wait_until(|| {
+        pool.current_jobs()
+            .contains(&Some("nightly-report".to_string()))
+    });
+    assert_eq!(pool.current_jobs().len(), 2);
+
+    release_tx.send(()).unwrap();
+    pool.wait_for_idle();
+    wait_until(|| pool.current_jobs().iter().all(Option::is_none));
+}
+
+#[test]
+fn utilization_is_a_fraction_per_worker() {
+    let pool = ThreadPool::new(3).unwrap();
+
+    for i in 0..6 {
+        pool.execute(move || thread::sleep(Duration::from_millis(5 * i)));
+    }
+    pool.wait_for_idle();
+
+    let utilization = pool.utilization();
+    assert_eq!(utilization.len(), 3);
+    assert!(utilization.iter().all(|u| (0.0..=1.0).contains(u)));
+    assert!(utilization.iter().any(|&u| u > 0.0));
+}
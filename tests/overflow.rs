@@ -0,0 +1,112 @@
+//! Tests of what `execute` does while a bounded queue is full.
+
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use yarws::{OverflowPolicy, ThreadPool, ThreadPoolBuilder};
+
+type Log = Arc<Mutex<Vec<&'static str>>>;
+
+/// Returns a job that appends `label` to `log`.
+fn record(log: &Log, label: &'static str) -> impl FnOnce() + Send + 'static {
+    let log = Arc::clone(log);
+
+    move || log.lock().unwrap().push(label)
+}
+
+/// Builds a one-worker pool with room for two queued jobs, busies its
+/// worker and fills the queue with "first" and "second". The worker is
+/// freed once the returned sender is dropped.
+fn full_pool(policy: OverflowPolicy, log: &Log) -> (ThreadPool, mpsc::Sender<()>) {
+    let pool = ThreadPoolBuilder::new()
+        .size(1)
+        .queue_capacity(2)
+        .overflow_policy(policy)
+        .build()
+        .unwrap();
+    let (started_tx, started_rx) = mpsc::channel();
+    let (release_tx, release_rx) = mpsc::channel::<()>();
+
+    pool.execute(move || {
+        started_tx.send(()).unwrap();
+        let _ = release_rx.recv();
+    });
+    started_rx.recv().unwrap();
+    pool.execute(record(log, "first"));
+    pool.execute(record(log, "second"));
+
+    (pool, release_tx)
+}
+
+#[test]
+fn drop_newest_discards_the_submitted_job() {
+    let log = Log::default();
+    let (pool, release) = full_pool(OverflowPolicy::DropNewest, &log);
+
+    pool.execute(record(&log, "third"));
+    drop(release);
+    pool.wait_for_idle();
+
+    assert_eq!(*log.lock().unwrap(), ["first", "second"]);
+    assert_eq!(pool.dropped_count(), 1);
+}
+
+#[test]
+fn drop_oldest_evicts_the_front_of_the_queue() {
+    let log = Log::default();
+    let (pool, release) = full_pool(OverflowPolicy::DropOldest, &log);
+
+    pool.execute(record(&log, "third"));
+    drop(release);
+    pool.wait_for_idle();
+
+    assert_eq!(*log.lock().unwrap(), ["second", "third"]);
+    assert_eq!(pool.dropped_count(), 1);
+}
+
+#[test]
+fn error_panics_without_queueing_the_job() {
+    let log = Log::default();
+    let (pool, release) = full_pool(OverflowPolicy::Error, &log);
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        pool.execute(record(&log, "third"));
+    }));
+    drop(release);
+    pool.wait_for_idle();
+
+    let payload = result.unwrap_err();
+    assert_eq!(
+        payload.downcast_ref::<String>().map(String::as_str),
+        Some("job queue is full")
+    );
+    assert_eq!(*log.lock().unwrap(), ["first", "second"]);
+    assert_eq!(pool.dropped_count(), 0);
+}
+
+#[test]
+fn block_waits_for_room_in_the_queue() {
+    let log = Log::default();
+    let (pool, release) = full_pool(OverflowPolicy::Block, &log);
+    let (submitted_tx, submitted_rx) = mpsc::channel();
+
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            pool.execute(record(&log, "third"));
+            submitted_tx.send(()).unwrap();
+        });
+        assert!(submitted_rx
+            .recv_timeout(Duration::from_millis(50))
+            .is_err());
+        drop(release);
+        submitted_rx.recv().unwrap();
+    });
+    pool.wait_for_idle();
+
+    assert_eq!(*log.lock().unwrap(), ["first", "second", "third"]);
+    assert_eq!(pool.dropped_count(), 0);
+}
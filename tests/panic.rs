@@ -0,0 +1,132 @@
+//! Tests of how a pool copes with jobs that panic.
+
+mod common;
+
+use std::{thread, time::Duration};
+
+use yarws::{ExecuteError, PanicPolicy, ThreadPool, ThreadPoolBuilder};
+
+use common::wait_until;
+
+/// Builds a one-worker pool that handles panics with `policy`.
+fn pool_with(policy: PanicPolicy) -> ThreadPool {
+    ThreadPoolBuilder::new()
+        .size(1)
+        .panic_handler(policy)
+        .build()
+        .unwrap()
+}
+
+/// Returns the id of the thread that runs the pool's next job.
+fn worker_thread(pool: &ThreadPool) -> thread::ThreadId {
+    pool.execute_with_result(|| thread::current().id())
+        .recv()
+        .unwrap()
+}
+
+#[test]
+fn worker_keeps_running_jobs_after_a_panic() {
+    let pool = ThreadPool::new(1).unwrap();
+
+    pool.execute(|| panic!("job failed"));
+    let after = pool.execute_with_result(|| 7);
+
+    assert_eq!(after.recv(), Ok(7));
+    assert_eq!(pool.size(), 1);
+}
+
+#[test]
+fn pool_keeps_running_jobs_after_many_panics() {
+    let pool = ThreadPool::new(4).unwrap();
+
+    for _ in 0..8 {
+        pool.execute(|| panic!("job failed"));
+    }
+    let receivers: Vec<_> = (0..100)
+        .map(|i| pool.execute_with_result(move || i))
+        .collect();
+
+    let sum: i32 = receivers.iter().map(|rx| rx.recv().unwrap()).sum();
+    assert_eq!(sum, 4950);
+    assert!(pool.is_healthy());
+}
+
+#[test]
+fn continue_policy_keeps_the_same_thread() {
+    let pool = pool_with(PanicPolicy::Continue);
+    let before = worker_thread(&pool);
+
+    pool.execute(|| panic!("job failed"));
+
+    assert_eq!(worker_thread(&pool), before);
+    assert_eq!(pool.size(), 1);
+}
+
+#[test]
+fn respawn_policy_replaces_the_thread() {
+    let pool = pool_with(PanicPolicy::Respawn);
+    let before = worker_thread(&pool);
+
+    pool.execute(|| panic!("job failed"));
+
+    assert_ne!(worker_thread(&pool), before);
+    assert_eq!(pool.size(), 1);
+}
+
+#[test]
+fn propagate_policy_loses_the_worker() {
+    let pool = pool_with(PanicPolicy::Propagate);
+    assert_eq!(pool.size(), 1);
+
+    pool.execute(|| panic!("job failed"));
+
+    wait_until(|| pool.size() == 0);
+    assert!(!pool.is_healthy());
+}
+
+#[test]
+fn is_healthy_turns_false_when_a_worker_dies() {
+    let pool = ThreadPoolBuilder::new()
+        .size(2)
+        .panic_handler(PanicPolicy::Propagate)
+        .build()
+        .unwrap();
+    assert!(pool.is_healthy());
+
+    pool.execute(|| panic!("job failed"));
+
+    wait_until(|| !pool.is_healthy());
+    assert_eq!(pool.size(), 1);
+    assert_eq!(pool.execute_with_result(|| 5).recv(), Ok(5));
+}
+
+#[test]
+fn try_execute_fails_once_every_worker_is_dead() {
+    let pool = ThreadPoolBuilder::new()
+        .size(2)
+        .panic_handler(PanicPolicy::Propagate)
+        .build()
+        .unwrap();
+
+    for _ in 0..2 {
+        pool.execute(|| panic!("job failed"));
+    }
+    wait_until(|| pool.size() == 0);
+
+    assert_eq!(pool.try_execute(|| {}), Err(ExecuteError::PoolDead));
+}
+
+#[test]
+fn dropping_a_pool_while_unwinding_does_not_abort() {
+    let result = thread::spawn(|| {
+        let pool = ThreadPool::new(2).unwrap();
+        pool.execute(|| thread::sleep(Duration::from_millis(20)));
+
+        // The pool is dropped while this panic unwinds the thread; a panic
+        // in its `Drop` would abort the whole test process.
+        panic!("caller failed");
+    })
+    .join();
+
+    assert!(result.is_err());
+}
@@ -0,0 +1,155 @@
+//! Tests of the helpers that split work across a pool.
+
+use std::{
+    sync::{
+        atomic::{AtomicI64, AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use yarws::ThreadPool;
+
+#[test]
+fn scoped_jobs_borrow_local_data() {
+    let pool = ThreadPool::new(4).unwrap();
+    let data: Vec<i32> = (1..=1000).collect();
+    let total = AtomicI64::new(0);
+
+    pool.scope(|scope| {
+        for chunk in data.chunks(100) {
+            let total = &total;
+            scope.execute(move || {
+                let sum: i64 = chunk.iter().map(|&x| i64::from(x)).sum();
+                total.fetch_add(sum, Ordering::SeqCst);
+            });
+        }
+    });
+
+    assert_eq!(total.into_inner(), 500_500);
+}
+
+#[test]
+fn map_keeps_the_input_order() {
+    let pool = ThreadPool::new(4).unwrap();
+
+    let doubled = pool.map((0..100).collect(), |x: u64| {
+        // Make later items tend to finish first.
+        thread::sleep(Duration::from_micros(100 - x));
+        x * 2
+    });
+
+    assert_eq!(doubled, (0..100).map(|x| x * 2).collect::<Vec<_>>());
+}
+
+#[test]
+fn for_each_chunk_squares_every_element() {
+    let pool = ThreadPool::new(4).unwrap();
+    let mut data = [0i32; 1000];
+    for (i, x) in data.iter_mut().enumerate() {
+        *x = i as i32;
+    }
+
+    pool.for_each_chunk(&mut data, 64, |chunk| {
+        for x in chunk {
+            *x *= *x;
+        }
+    });
+
+    assert!(data.iter().enumerate().all(|(i, &x)| x == (i * i) as i32));
+}
+
+/// Sums `values` by splitting it in half with `fork_join` until the pieces
+/// are small.
+fn parallel_sum(pool: &ThreadPool, values: &[u64]) -> u64 {
+    if values.len() <= 1024 {
+        return values.iter().sum();
+    }
+
+    let (left, right) = values.split_at(values.len() / 2);
+    let (a, b) = pool.fork_join(|| left.iter().sum::<u64>(), || parallel_sum(pool, right));
+    a + b
+}
+
+#[test]
+fn fork_join_sums_recursively_on_one_worker() {
+    let values: Vec<u64> = (1..=100_000).collect();
+
+    for size in [1, 4] {
+        let pool = ThreadPool::new(size).unwrap();
+        assert_eq!(parallel_sum(&pool, &values), 5_000_050_000);
+    }
+}
+
+#[test]
+fn pipeline_doubles_every_item() {
+    let pool = ThreadPool::new(4).unwrap();
+    let (input, items) = mpsc::channel();
+
+    let output = pool.pipeline(items, |x: u32| x * 2);
+    for x in 0..200 {
+        input.send(x).unwrap();
+    }
+    drop(input);
+
+    let mut doubled: Vec<_> = output.iter().collect();
+    doubled.sort_unstable();
+    assert_eq!(doubled, (0..200).map(|x| x * 2).collect::<Vec<_>>());
+}
+
+#[test]
+fn collector_returns_results_in_index_order() {
+    let pool = ThreadPool::new(4).unwrap();
+    let collector = pool.collector();
+
+    for index in [3, 0, 4, 1, 2] {
+        collector.submit(index, move || {
+            thread::sleep(Duration::from_millis(5 * (5 - index as u64)));
+            index * 10
+        });
+    }
+
+    assert_eq!(collector.collect(), [0, 10, 20, 30, 40]);
+}
+
+#[test]
+fn scatter_gather_runs_tasks_in_parallel_and_in_order() {
+    let pool = ThreadPool::new(4).unwrap();
+    let tasks: Vec<_> = (0..8)
+        .map(|i| {
+            move || {
+                thread::sleep(Duration::from_millis(50));
+                i
+            }
+        })
+        .collect();
+
+    let start = Instant::now();
+    let results = pool.scatter_gather(tasks);
+
+    assert_eq!(results, (0..8).collect::<Vec<_>>());
+    assert!(start.elapsed() < Duration::from_millis(8 * 50));
+}
+
+#[test]
+fn scatter_gather_finishes_on_one_worker() {
+    let pool = ThreadPool::new(1).unwrap();
+
+    let results = pool.scatter_gather((0..8).map(|i| move || i * 2).collect());
+
+    assert_eq!(results, (0..8).map(|i| i * 2).collect::<Vec<_>>());
+}
+
+#[test]
+fn broadcast_runs_once_on_every_worker() {
+    let pool = ThreadPool::new(4).unwrap();
+    let runs: Arc<Vec<AtomicUsize>> = Arc::new((0..4).map(|_| AtomicUsize::new(0)).collect());
+
+    let counters = Arc::clone(&runs);
+    pool.broadcast(move |id| {
+        counters[id].fetch_add(1, Ordering::SeqCst);
+    });
+
+    assert!(runs.iter().all(|count| count.load(Ordering::SeqCst) == 1));
+}
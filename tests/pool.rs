@@ -0,0 +1,170 @@
+//! Tests of creating pools and of what they report about themselves.
+
+use std::{
+    cell::Cell,
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+};
+
+use yarws::{PoolCreationError, ThreadPool, ThreadPoolBuilder};
+
+#[test]
+fn new_rejects_size_zero() {
+    assert_eq!(ThreadPool::new(0).err(), Some(PoolCreationError::ZeroSize));
+}
+
+#[test]
+fn builder_applies_stack_size_and_name_prefix() {
+    let pool = ThreadPoolBuilder::new()
+        .size(2)
+        .stack_size(2 * 1024 * 1024)
+        .thread_name_prefix("worker-")
+        .build()
+        .unwrap();
+
+    let name = pool
+        .execute_with_result(|| thread::current().name().map(str::to_string))
+        .recv()
+        .unwrap();
+
+    assert!(name.unwrap().starts_with("worker-"));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn pinned_workers_run_every_job() {
+    let pool = ThreadPoolBuilder::new()
+        .size(4)
+        .pin_to_cores(true)
+        .build()
+        .unwrap();
+
+    let receivers: Vec<_> = (0..100)
+        .map(|i| pool.execute_with_result(move || i))
+        .collect();
+    let sum: i32 = receivers.iter().map(|rx| rx.recv().unwrap()).sum();
+
+    assert_eq!(sum, 4950);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn low_priority_workers_run_jobs() {
+    let pool = ThreadPoolBuilder::new()
+        .size(2)
+        .thread_priority(yarws::ThreadPriority::Low)
+        .build()
+        .unwrap();
+
+    assert_eq!(pool.execute_with_result(|| 3 * 3).recv(), Ok(9));
+}
+
+#[test]
+fn default_pool_has_a_worker_and_runs_jobs() {
+    let pool = ThreadPool::default();
+
+    assert!(pool.size() >= 1);
+    assert_eq!(pool.execute_with_result(|| 1 + 1).recv(), Ok(2));
+}
+
+#[test]
+fn from_channel_runs_jobs_sent_on_the_channel() {
+    let (job_tx, job_rx) = mpsc::channel::<Box<dyn FnOnce() + Send + 'static>>();
+    let (done_tx, done_rx) = mpsc::channel();
+    let _pool = ThreadPool::from_channel(2, job_rx).unwrap();
+
+    job_tx
+        .send(Box::new(move || done_tx.send("ran").unwrap()))
+        .unwrap();
+
+    assert_eq!(done_rx.recv(), Ok("ran"));
+}
+
+#[test]
+fn debug_output_shows_the_worker_count() {
+    let pool = ThreadPool::new(3).unwrap();
+
+    let debug = format!("{:?}", pool);
+
+    assert!(debug.contains("size: 3"), "{}", debug);
+}
+
+#[test]
+fn warmed_workers_run_jobs() {
+    let pool = ThreadPoolBuilder::new()
+        .size(2)
+        .warm_allocator(true)
+        .build()
+        .unwrap();
+
+    let lengths: Vec<_> = (0..10)
+        .map(|i| pool.execute_with_result(move || vec![0u8; 1024 * i].len()))
+        .map(|rx| rx.recv().unwrap())
+        .collect();
+
+    assert_eq!(lengths, (0..10).map(|i| 1024 * i).collect::<Vec<_>>());
+}
+
+thread_local! {
+    static WORKER_ID: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+#[test]
+fn worker_init_sets_up_thread_locals() {
+    let pool = ThreadPoolBuilder::new()
+        .size(1)
+        .worker_init(Box::new(|id| WORKER_ID.with(|cell| cell.set(Some(id)))))
+        .build()
+        .unwrap();
+
+    let id = pool
+        .execute_with_result(|| WORKER_ID.with(Cell::get))
+        .recv()
+        .unwrap();
+
+    assert_eq!(id, Some(0));
+}
+
+#[test]
+fn max_jobs_per_worker_recycles_the_thread() {
+    let spawns = Arc::new(AtomicUsize::new(0));
+    let pool = {
+        let spawns = Arc::clone(&spawns);
+        ThreadPoolBuilder::new()
+            .size(1)
+            .max_jobs_per_worker(5)
+            .worker_init(Box::new(move |_| {
+                spawns.fetch_add(1, Ordering::SeqCst);
+            }))
+            .build()
+            .unwrap()
+    };
+    let (tx, rx) = mpsc::channel();
+
+    for _ in 0..20 {
+        let tx = tx.clone();
+        pool.execute(move || tx.send(thread::current().id()).unwrap());
+    }
+    drop(tx);
+    let threads: Vec<_> = rx.iter().collect();
+
+    assert_eq!(threads.len(), 20);
+    assert_eq!(threads.iter().collect::<HashSet<_>>().len(), 4);
+    assert!(spawns.load(Ordering::SeqCst) >= 4);
+    assert_eq!(pool.size(), 1);
+}
+
+#[test]
+fn panicking_worker_init_fails_the_build() {
+    let result = ThreadPoolBuilder::new()
+        .size(2)
+        .suppress_panic_output(true)
+        .worker_init(Box::new(|_| panic!("no connection")))
+        .build();
+
+    assert_eq!(result.err(), Some(PoolCreationError::InitPanicked));
+}
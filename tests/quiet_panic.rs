@@ -0,0 +1,47 @@
+//! Checks that `suppress_panic_output` keeps caught panics off stderr. The
+//! pool runs in a child process so its stderr can be read.
+
+use std::{env, process::Command};
+
+use yarws::ThreadPoolBuilder;
+
+const CHILD_ENV: &str = "YARWS_QUIET_PANIC_CHILD";
+
+fn child() {
+    let pool = ThreadPoolBuilder::new()
+        .size(2)
+        .suppress_panic_output(true)
+        .build()
+        .unwrap();
+
+    for _ in 0..3 {
+        pool.execute(|| panic!("noisy job"));
+    }
+    pool.wait_for_idle();
+
+    println!("panicked {}", pool.stats().total_panicked);
+}
+
+#[test]
+fn caught_panics_are_counted_but_not_printed() {
+    if env::var_os(CHILD_ENV).is_some() {
+        child();
+        return;
+    }
+
+    let output = Command::new(env::current_exe().unwrap())
+        .args([
+            "caught_panics_are_counted_but_not_printed",
+            "--exact",
+            "--nocapture",
+        ])
+        .env(CHILD_ENV, "1")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stdout.contains("panicked 3\n"), "{:?}", stdout);
+    assert!(!stderr.contains("noisy job"), "{:?}", stderr);
+}
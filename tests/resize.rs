@@ -0,0 +1,213 @@
+//! Tests of growing and shrinking a running pool.
+
+mod common;
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Barrier,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use yarws::{PoolCreationError, ResizeError, ThreadPool, ThreadPoolBuilder};
+
+use common::wait_until;
+
+/// Returns how long `pool` takes to run `jobs` jobs that each sleep 50 ms.
+fn time_sleeps(pool: &ThreadPool, jobs: usize) -> Duration {
+    let start = Instant::now();
+
+    for _ in 0..jobs {
+        pool.execute(|| thread::sleep(Duration::from_millis(50)));
+    }
+    pool.wait_for_idle();
+
+    start.elapsed()
+}
+
+#[test]
+fn add_workers_increases_throughput() {
+    let mut pool = ThreadPool::new(2).unwrap();
+    let before = time_sleeps(&pool, 12);
+
+    pool.add_workers(4);
+    let after = time_sleeps(&pool, 12);
+
+    assert_eq!(pool.size(), 6);
+    assert!(after * 2 < before, "{:?} vs {:?}", after, before);
+}
+
+#[test]
+fn remove_workers_joins_the_removed_threads() {
+    let cleaned_up = Arc::new(AtomicUsize::new(0));
+    let mut pool = {
+        let cleaned_up = Arc::clone(&cleaned_up);
+
+        ThreadPoolBuilder::new()
+            .size(4)
+            .worker_cleanup(Box::new(move |_| {
+                cleaned_up.fetch_add(1, Ordering::SeqCst);
+            }))
+            .build()
+            .unwrap()
+    };
+
+    pool.remove_workers(3).unwrap();
+
+    // Cleanup runs on the worker thread just before it exits, so it has run
+    // three times only if all three threads were joined.
+    assert_eq!(cleaned_up.load(Ordering::SeqCst), 3);
+    assert_eq!(pool.size(), 1);
+    assert_eq!(pool.worker_info().len(), 1);
+}
+
+#[test]
+fn remove_workers_does_not_wait_for_the_backlog() {
+    let mut pool = ThreadPool::new(2).unwrap();
+
+    for _ in 0..20 {
+        pool.execute(|| thread::sleep(Duration::from_millis(100)));
+    }
+
+    let start = Instant::now();
+    pool.remove_workers(1).unwrap();
+
+    assert!(start.elapsed() < Duration::from_millis(500));
+    assert_eq!(pool.size(), 1);
+    pool.clear_queue();
+}
+
+#[test]
+fn remove_workers_keeps_at_least_one_worker() {
+    let mut pool = ThreadPool::new(2).unwrap();
+
+    assert_eq!(
+        pool.remove_workers(2),
+        Err(ResizeError::TooFewWorkers { removable: 1 })
+    );
+    assert_eq!(pool.size(), 2);
+}
+
+#[test]
+fn remove_workers_keeps_the_minimum_size() {
+    let mut pool = ThreadPoolBuilder::new()
+        .size(4)
+        .min_size(2)
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        pool.remove_workers(3),
+        Err(ResizeError::TooFewWorkers { removable: 2 })
+    );
+    assert_eq!(pool.set_size(1), Err(PoolCreationError::SizeBounds));
+    assert_eq!(pool.size(), 4);
+}
+
+#[test]
+fn idle_workers_shrink_to_the_minimum_and_grow_back() {
+    let pool = ThreadPoolBuilder::new()
+        .size(4)
+        .min_size(1)
+        .idle_timeout(Duration::from_millis(50))
+        .build()
+        .unwrap();
+
+    wait_until(|| pool.size() == 1);
+
+    let all_running = Arc::new(Barrier::new(5));
+    for _ in 0..4 {
+        let all_running = Arc::clone(&all_running);
+        pool.execute(move || {
+            all_running.wait();
+        });
+    }
+    all_running.wait();
+
+    assert_eq!(pool.size(), 4);
+}
+
+#[test]
+fn set_size_grows_and_shrinks_to_the_exact_size() {
+    let mut pool = ThreadPool::new(4).unwrap();
+
+    pool.set_size(8).unwrap();
+    assert_eq!(pool.size(), 8);
+
+    pool.set_size(2).unwrap();
+    assert_eq!(pool.size(), 2);
+    assert_eq!(pool.worker_info().len(), 2);
+}
+
+#[test]
+fn set_size_rejects_zero() {
+    let mut pool = ThreadPool::new(4).unwrap();
+
+    assert_eq!(pool.set_size(0), Err(PoolCreationError::ZeroSize));
+    assert_eq!(pool.size(), 4);
+}
+
+#[test]
+fn set_size_rejects_a_size_above_max_size() {
+    let mut elastic = ThreadPoolBuilder::new()
+        .size(2)
+        .max_size(4)
+        .build()
+        .unwrap();
+    let mut scaled = ThreadPoolBuilder::new()
+        .size(2)
+        .max_size(4)
+        .auto_scale(Duration::from_secs(60))
+        .build()
+        .unwrap();
+
+    for pool in [&mut elastic, &mut scaled] {
+        assert_eq!(pool.set_size(5), Err(PoolCreationError::SizeBounds));
+        assert_eq!(pool.size(), 2);
+
+        pool.set_size(4).unwrap();
+        assert_eq!(pool.size(), 4);
+    }
+}
+
+#[test]
+fn auto_scale_grows_under_a_backlog_and_shrinks_once_it_clears() {
+    let pool = ThreadPoolBuilder::new()
+        .size(1)
+        .max_size(4)
+        .auto_scale(Duration::from_millis(5))
+        .build()
+        .unwrap();
+
+    for _ in 0..100 {
+        pool.execute(|| thread::sleep(Duration::from_millis(10)));
+    }
+    wait_until(|| pool.size() > 1);
+    pool.wait_for_idle();
+
+    wait_until(|| pool.size() == 1);
+}
+
+#[test]
+fn manual_removal_and_auto_scale_together_keep_the_minimum_size() {
+    let mut pool = ThreadPoolBuilder::new()
+        .size(4)
+        .min_size(2)
+        .auto_scale(Duration::from_millis(1))
+        .build()
+        .unwrap();
+
+    // The scaler starts removing idle workers straight away, so this may
+    // find some of them already on their way out.
+    let _ = pool.remove_workers(2);
+    wait_until(|| pool.size() == 2);
+    thread::sleep(Duration::from_millis(50));
+
+    assert_eq!(pool.size(), 2);
+    assert_eq!(
+        pool.remove_workers(1),
+        Err(ResizeError::TooFewWorkers { removable: 0 })
+    );
+}
@@ -0,0 +1,220 @@
+//! Tests of the order in which queued jobs are run.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, ThreadId},
+    time::Duration,
+};
+
+use yarws::{Dispatch, PoolEvent, ThreadPool, ThreadPoolBuilder};
+
+type Log = Arc<Mutex<Vec<&'static str>>>;
+
+/// Returns a job that appends `label` to `log`.
+fn record(log: &Log, label: &'static str) -> impl FnOnce() + Send + 'static {
+    let log = Arc::clone(log);
+
+    move || log.lock().unwrap().push(label)
+}
+
+#[test]
+fn higher_priority_jobs_run_first() {
+    let pool = ThreadPool::new(1).unwrap();
+    let log = Log::default();
+
+    pool.pause();
+    for _ in 0..3 {
+        pool.execute_with_priority(0, record(&log, "low"));
+    }
+    pool.execute_with_priority(5, record(&log, "high"));
+    pool.resume();
+    pool.wait_for_idle();
+
+    assert_eq!(*log.lock().unwrap(), ["high", "low", "low", "low"]);
+}
+
+#[test]
+fn every_job_runs_exactly_once() {
+    const JOBS: usize = 100_000;
+
+    let pool = ThreadPool::new(4).unwrap();
+    let runs: Arc<Vec<AtomicUsize>> = Arc::new((0..JOBS).map(|_| AtomicUsize::new(0)).collect());
+
+    for i in 0..JOBS {
+        let runs = Arc::clone(&runs);
+        pool.execute(move || {
+            runs[i].fetch_add(1, Ordering::SeqCst);
+        });
+    }
+    pool.wait_for_idle();
+
+    assert!(runs.iter().all(|count| count.load(Ordering::SeqCst) == 1));
+    assert_eq!(pool.stats().total_completed, JOBS as u64);
+}
+
+#[test]
+fn round_robin_gives_each_worker_one_job() {
+    const WORKERS: usize = 4;
+
+    let pool = ThreadPoolBuilder::new()
+        .size(WORKERS)
+        .dispatch(Dispatch::RoundRobin)
+        .build()
+        .unwrap();
+    let handled: Arc<HashMap<_, _>> = Arc::new(
+        pool.worker_info()
+            .into_iter()
+            .map(|info| (info.thread_id, AtomicUsize::new(0)))
+            .collect(),
+    );
+
+    for _ in 0..WORKERS {
+        let handled = Arc::clone(&handled);
+        pool.execute(move || {
+            handled[&thread::current().id()].fetch_add(1, Ordering::SeqCst);
+        });
+    }
+    pool.wait_for_idle();
+
+    assert_eq!(handled.len(), WORKERS);
+    assert!(handled
+        .values()
+        .all(|count| count.load(Ordering::SeqCst) == 1));
+}
+
+#[test]
+fn single_worker_runs_jobs_in_submission_order() {
+    let pool = ThreadPool::new(1).unwrap();
+    let order = Arc::new(Mutex::new(Vec::new()));
+
+    for i in 0..10_000 {
+        let order = Arc::clone(&order);
+        pool.execute(move || order.lock().unwrap().push(i));
+    }
+    pool.wait_for_idle();
+
+    assert!(order.lock().unwrap().iter().copied().eq(0..10_000));
+}
+
+#[test]
+fn immediate_pool_runs_jobs_in_order_on_the_caller() {
+    let pool = ThreadPool::immediate();
+    let log = Log::default();
+    let caller = thread::current().id();
+
+    pool.execute(record(&log, "first"));
+    pool.execute(move || assert_eq!(thread::current().id(), caller));
+    pool.execute(record(&log, "second"));
+
+    assert_eq!(*log.lock().unwrap(), ["first", "second"]);
+    assert_eq!(pool.stats().total_panicked, 0);
+}
+
+#[test]
+fn fifo_start_starts_jobs_in_submission_order() {
+    // `JobStarted` is emitted on the worker while it holds the job's turn, so
+    // the event log is the start order. Each worker runs its jobs one after
+    // another, so its n-th event belongs to the n-th job it ran.
+    let started: Arc<Mutex<Vec<ThreadId>>> = Arc::default();
+    let sink = Arc::clone(&started);
+    let pool = ThreadPoolBuilder::new()
+        .size(4)
+        .dispatch(Dispatch::FifoStart)
+        .on_event(Box::new(move |event| {
+            if let PoolEvent::JobStarted { .. } = event {
+                sink.lock().unwrap().push(thread::current().id());
+            }
+        }))
+        .build()
+        .unwrap();
+    let ran: Arc<Mutex<HashMap<ThreadId, VecDeque<usize>>>> = Arc::default();
+
+    pool.pause();
+    for i in 0..200 {
+        let ran = Arc::clone(&ran);
+        pool.execute(move || {
+            thread::sleep(Duration::from_micros((i % 7) as u64 * 50));
+            ran.lock()
+                .unwrap()
+                .entry(thread::current().id())
+                .or_default()
+                .push_back(i);
+        });
+    }
+    pool.resume();
+    pool.wait_for_idle();
+
+    let mut ran = ran.lock().unwrap();
+    let order: Vec<usize> = started
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|thread| ran.get_mut(thread).unwrap().pop_front().unwrap())
+        .collect();
+    assert_eq!(order, (0..200).collect::<Vec<_>>());
+}
+
+#[test]
+fn paused_pool_holds_jobs_until_resumed() {
+    let pool = ThreadPool::new(2).unwrap();
+    let count = Arc::new(AtomicUsize::new(0));
+
+    pool.pause();
+    for _ in 0..10 {
+        let count = Arc::clone(&count);
+        pool.execute(move || {
+            count.fetch_add(1, Ordering::SeqCst);
+        });
+    }
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(count.load(Ordering::SeqCst), 0);
+    assert_eq!(pool.queued_count(), 10);
+
+    pool.resume();
+    pool.wait_for_idle();
+
+    assert_eq!(count.load(Ordering::SeqCst), 10);
+}
+
+#[test]
+fn clear_queue_discards_jobs_that_have_not_started() {
+    let pool = ThreadPool::new(1).unwrap();
+    let count = Arc::new(AtomicUsize::new(0));
+
+    pool.pause();
+    for _ in 0..100 {
+        let count = Arc::clone(&count);
+        pool.execute(move || {
+            count.fetch_add(1, Ordering::SeqCst);
+        });
+    }
+
+    assert_eq!(pool.clear_queue(), 100);
+    pool.resume();
+    pool.wait_for_idle();
+
+    assert_eq!(count.load(Ordering::SeqCst), 0);
+    assert_eq!(pool.queued_count(), 0);
+}
+
+#[test]
+fn a_million_tiny_jobs_all_complete() {
+    const JOBS: usize = 1_000_000;
+
+    let pool = ThreadPool::new(8).unwrap();
+    let count = Arc::new(AtomicUsize::new(0));
+
+    for _ in 0..JOBS {
+        let count = Arc::clone(&count);
+        pool.execute(move || {
+            count.fetch_add(1, Ordering::Relaxed);
+        });
+    }
+    pool.wait_for_idle();
+
+    assert_eq!(count.load(Ordering::SeqCst), JOBS);
+}
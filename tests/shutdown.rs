@@ -0,0 +1,192 @@
+//! Tests of the ways a pool shuts down.
+
+mod common;
+
+use std::{
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use yarws::{
+    ExecuteError, PanicPolicy, ShutdownError, ShutdownOrder, ThreadPool, ThreadPoolBuilder,
+};
+
+use common::wait_until;
+
+#[test]
+fn shutdown_timeout_reports_stuck_workers() {
+    let pool = ThreadPool::new(2).unwrap();
+    let (started_tx, started_rx) = mpsc::channel();
+
+    pool.execute(move || {
+        started_tx.send(()).unwrap();
+        thread::sleep(Duration::from_millis(500));
+    });
+    started_rx.recv().unwrap();
+
+    assert_eq!(
+        pool.shutdown_timeout(Duration::from_millis(50)),
+        Err(ShutdownError::Timeout { remaining: 1 })
+    );
+}
+
+#[test]
+fn try_execute_racing_shutdown_reports_shut_down() {
+    let pool = ThreadPool::new(2).unwrap();
+    let handle = pool.handle();
+
+    let submitter = thread::spawn(move || loop {
+        if let Err(err) = handle.try_execute(|| {}) {
+            return err;
+        }
+    });
+    thread::sleep(Duration::from_millis(10));
+    drop(pool);
+
+    assert_eq!(submitter.join().unwrap(), ExecuteError::ShutDown);
+}
+
+#[test]
+fn dropping_a_busy_pool_never_hangs() {
+    for _ in 0..100 {
+        let pool = ThreadPool::new(16).unwrap();
+        for _ in 0..64 {
+            pool.execute(|| {});
+        }
+        drop(pool);
+    }
+}
+
+#[test]
+fn join_reports_every_worker_joined() {
+    let pool = ThreadPool::new(4).unwrap();
+    pool.execute(|| {});
+
+    let summary = pool.join();
+
+    assert_eq!(summary.workers_joined, 4);
+    assert!(summary.panicked_workers.is_empty());
+}
+
+#[test]
+fn shutdown_draining_runs_every_queued_job() {
+    let pool = ThreadPool::new(2).unwrap();
+    let (tx, rx) = mpsc::channel();
+
+    for i in 0..50 {
+        let tx = tx.clone();
+        pool.execute(move || {
+            thread::sleep(Duration::from_millis(1));
+            tx.send(i).unwrap();
+        });
+    }
+    pool.shutdown_draining();
+
+    assert_eq!(rx.try_iter().count(), 50);
+}
+
+#[test]
+fn shutdown_now_returns_the_queued_jobs() {
+    let pool = ThreadPool::new(1).unwrap();
+
+    for _ in 0..20 {
+        pool.execute(|| thread::sleep(Duration::from_millis(20)));
+    }
+    let abandoned = pool.shutdown_now();
+
+    assert!(!abandoned.is_empty());
+}
+
+#[test]
+fn worker_cleanup_runs_once_per_worker() {
+    let cleaned_up = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&cleaned_up);
+    let pool = ThreadPoolBuilder::new()
+        .size(4)
+        .worker_cleanup(Box::new(move |id| sink.lock().unwrap().push(id)))
+        .build()
+        .unwrap();
+
+    drop(pool);
+
+    let mut ids = cleaned_up.lock().unwrap().clone();
+    ids.sort_unstable();
+    assert_eq!(ids, [0, 1, 2, 3]);
+}
+
+/// Returns the order in which the workers of a pool shut down in `order`
+/// ran their cleanup.
+fn cleanup_order(order: ShutdownOrder) -> Vec<usize> {
+    let (tx, rx) = mpsc::channel();
+    let pool = ThreadPoolBuilder::new()
+        .size(4)
+        .shutdown_order(order)
+        .worker_cleanup(Box::new(move |id| tx.send(id).unwrap()))
+        .build()
+        .unwrap();
+
+    drop(pool);
+
+    rx.try_iter().collect()
+}
+
+#[test]
+fn workers_stop_in_the_configured_order() {
+    assert_eq!(cleanup_order(ShutdownOrder::Fifo), [0, 1, 2, 3]);
+    assert_eq!(cleanup_order(ShutdownOrder::Lifo), [3, 2, 1, 0]);
+}
+
+#[test]
+fn explicit_shutdown_then_drop_is_a_no_op() {
+    let mut pool = ThreadPool::new(2).unwrap();
+    pool.execute(|| {});
+
+    let first = pool.shutdown();
+    let second = pool.shutdown();
+    drop(pool);
+
+    assert_eq!(first.workers_joined, 2);
+    assert_eq!(second.workers_joined, 0);
+}
+
+#[test]
+fn restarted_pool_runs_new_jobs() {
+    let mut pool = ThreadPool::new(3).unwrap();
+    pool.shutdown();
+
+    pool.restart().unwrap();
+
+    assert_eq!(pool.size(), 3);
+    assert_eq!(pool.execute_with_result(|| "again").recv(), Ok("again"));
+}
+
+#[test]
+fn restart_brings_back_only_the_live_workers() {
+    let mut pool = ThreadPoolBuilder::new()
+        .size(3)
+        .panic_handler(PanicPolicy::Propagate)
+        .build()
+        .unwrap();
+
+    pool.execute(|| panic!("job failed"));
+    wait_until(|| pool.size() < 3);
+
+    pool.restart().unwrap();
+
+    assert_eq!(pool.size(), 2);
+}
+
+#[test]
+fn restarted_immediate_pool_still_runs_jobs_inline() {
+    let mut pool = ThreadPool::immediate();
+    let caller = thread::current().id();
+
+    pool.restart().unwrap();
+
+    assert_eq!(pool.size(), 0);
+    assert_eq!(
+        pool.execute_with_result(|| thread::current().id()).recv(),
+        Ok(caller)
+    );
+}
@@ -0,0 +1,63 @@
+//! Checks that a signal drains the pool. Run with
+//! `cargo test --features signal --test signal`.
+
+#![cfg(all(unix, feature = "signal"))]
+
+use std::{
+    env,
+    os::unix::process::ExitStatusExt,
+    process::{Command, Stdio},
+    thread,
+    time::Duration,
+};
+
+use signal_hook::{consts::SIGTERM, low_level};
+use yarws::ThreadPool;
+
+const CHILD_ENV: &str = "YARWS_SIGNAL_CHILD";
+const JOBS: usize = 4;
+
+/// Runs in a child process, since the signal ends up terminating it.
+fn child() {
+    let pool = ThreadPool::new(2).unwrap();
+    pool.install_signal_handler().unwrap();
+
+    for i in 0..JOBS {
+        pool.execute(move || {
+            thread::sleep(Duration::from_millis(200));
+            println!("finished {}", i);
+        });
+    }
+
+    low_level::raise(SIGTERM).unwrap();
+
+    // The handler re-raises SIGTERM once the pool has drained.
+    thread::sleep(Duration::from_secs(30));
+    unreachable!("the process was not terminated");
+}
+
+#[test]
+fn sigterm_drains_the_pool() {
+    if env::var_os(CHILD_ENV).is_some() {
+        child();
+    }
+
+    let output = Command::new(env::current_exe().unwrap())
+        .args(["sigterm_drains_the_pool", "--exact", "--nocapture"])
+        .env(CHILD_ENV, "1")
+        .stderr(Stdio::inherit())
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.signal(), Some(SIGTERM));
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    for i in 0..JOBS {
+        assert!(
+            stdout.contains(&format!("finished {}\n", i)),
+            "job {} did not finish: {:?}",
+            i,
+            stdout
+        );
+    }
+}
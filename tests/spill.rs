@@ -0,0 +1,65 @@
+//! Tests of spilling registered tasks to disk and loading them back.
+
+use std::{
+    env,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use yarws::{TaskId, ThreadPool, ThreadPoolBuilder};
+
+const TASKS: usize = 10_000;
+const COUNT: TaskId = TaskId(1);
+
+/// Builds a one-worker pool that spills past 100 queued tasks, and whose
+/// `COUNT` task bumps the counter at the index in its payload.
+fn counting_pool(runs: &Arc<Vec<AtomicUsize>>) -> ThreadPool {
+    let runs = Arc::clone(runs);
+
+    ThreadPoolBuilder::new()
+        .size(1)
+        .spill_to_disk(100, env::temp_dir())
+        .task_handler(
+            COUNT,
+            Box::new(move |payload| {
+                let index = u32::from_le_bytes(payload.try_into().unwrap());
+                runs[index as usize].fetch_add(1, Ordering::SeqCst);
+            }),
+        )
+        .build()
+        .unwrap()
+}
+
+fn counters() -> Arc<Vec<AtomicUsize>> {
+    Arc::new((0..TASKS).map(|_| AtomicUsize::new(0)).collect())
+}
+
+fn submit_all(pool: &ThreadPool) {
+    for i in 0..TASKS as u32 {
+        pool.execute_task(COUNT, i.to_le_bytes().to_vec());
+    }
+}
+
+#[test]
+fn spilled_tasks_each_run_exactly_once() {
+    let runs = counters();
+    let pool = counting_pool(&runs);
+
+    submit_all(&pool);
+    pool.wait_for_idle();
+
+    assert!(runs.iter().all(|count| count.load(Ordering::SeqCst) == 1));
+}
+
+#[test]
+fn shutdown_runs_tasks_still_on_disk() {
+    let runs = counters();
+    let pool = counting_pool(&runs);
+
+    submit_all(&pool);
+    pool.join();
+
+    assert!(runs.iter().all(|count| count.load(Ordering::SeqCst) == 1));
+}
@@ -0,0 +1,55 @@
+//! Tests of delayed and recurring jobs.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use yarws::ThreadPool;
+
+#[test]
+fn execute_after_waits_for_the_delay() {
+    let pool = ThreadPool::new(2).unwrap();
+    let ran = Arc::new(AtomicBool::new(false));
+
+    {
+        let ran = Arc::clone(&ran);
+        pool.execute_after(Duration::from_millis(100), move || {
+            ran.store(true, Ordering::SeqCst);
+        });
+    }
+
+    thread::sleep(Duration::from_millis(50));
+    assert!(!ran.load(Ordering::SeqCst));
+
+    thread::sleep(Duration::from_millis(150));
+    assert!(ran.load(Ordering::SeqCst));
+}
+
+#[test]
+fn execute_every_repeats_until_cancelled() {
+    let pool = ThreadPool::new(2).unwrap();
+    let count = Arc::new(AtomicUsize::new(0));
+
+    let task = {
+        let count = Arc::clone(&count);
+        pool.execute_every(Duration::from_millis(100), move || {
+            count.fetch_add(1, Ordering::SeqCst);
+        })
+    };
+
+    thread::sleep(Duration::from_millis(350));
+    task.cancel();
+    let runs = count.load(Ordering::SeqCst);
+    assert!(runs >= 3, "ran {} times", runs);
+
+    // A run that was already due may still finish after the cancel.
+    thread::sleep(Duration::from_millis(50));
+    let after_cancel = count.load(Ordering::SeqCst);
+    thread::sleep(Duration::from_millis(250));
+    assert_eq!(count.load(Ordering::SeqCst), after_cancel);
+}
@@ -0,0 +1,72 @@
+//! Checks that each job runs inside a `job` span. Run with
+//! `cargo test --features tracing --test tracing`.
+
+#![cfg(feature = "tracing")]
+
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+};
+
+use tracing::{
+    span::{Attributes, Id, Record},
+    Event, Metadata, Subscriber,
+};
+use yarws::ThreadPool;
+
+/// Records which spans are created, entered and exited, in order.
+#[derive(Default)]
+struct SpanLog {
+    next_id: AtomicU64,
+    log: Mutex<Vec<String>>,
+}
+
+impl SpanLog {
+    fn push(&self, entry: String) {
+        self.log.lock().unwrap().push(entry);
+    }
+}
+
+impl Subscriber for SpanLog {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let id = Id::from_u64(self.next_id.fetch_add(1, Ordering::SeqCst) + 1);
+        self.push(format!("new {} {}", id.into_u64(), span.metadata().name()));
+        id
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, _: &Event<'_>) {
+        self.push("event".to_string());
+    }
+
+    fn enter(&self, span: &Id) {
+        self.push(format!("enter {}", span.into_u64()));
+    }
+
+    fn exit(&self, span: &Id) {
+        self.push(format!("exit {}", span.into_u64()));
+    }
+}
+
+#[test]
+fn job_runs_inside_a_job_span() {
+    let spans = Arc::new(SpanLog::default());
+    tracing::subscriber::set_global_default(Arc::clone(&spans)).unwrap();
+    let pool = ThreadPool::new(1).unwrap();
+
+    pool.execute(|| tracing::info!("inside"));
+    pool.wait_for_idle();
+
+    let log = spans.log.lock().unwrap();
+    let inside = log.iter().position(|entry| entry == "event").unwrap();
+    assert_eq!(log[..inside].last().map(String::as_str), Some("enter 1"));
+    assert_eq!(log[inside + 1], "exit 1");
+    assert_eq!(log[0], "new 1 job");
+}